@@ -0,0 +1,115 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! SHA-256 compression using the ARMv8 SHA2 crypto extension. `sha256h` and
+//! `sha256h2` each advance one half of the state by four rounds, and
+//! `sha256su0`/`sha256su1` extend the message schedule four words at a time.
+
+#![allow(unsafe_code)]
+
+use core::arch::aarch64::*;
+
+use crate::CBRT_CONST;
+
+#[cfg(feature = "std")]
+pub(crate) fn is_supported() -> bool {
+    std::arch::is_aarch64_feature_detected!("sha2")
+}
+
+/// Without `std` there is no runtime detection, so the extension is only used
+/// when the build targets it.
+#[cfg(not(feature = "std"))]
+pub(crate) fn is_supported() -> bool {
+    cfg!(target_feature = "sha2")
+}
+
+/// Compresses `blocks` into `hash` if the CPU has the SHA2 extension, returning
+/// whether it did.
+pub(crate) fn try_compress(hash: &mut [u32; 8], blocks: &[u8]) -> bool {
+    if !is_supported() {
+        return false;
+    }
+    // SAFETY: the CPU features `compress` needs were detected.
+    unsafe { compress(hash, blocks) };
+    true
+}
+
+/// # Safety
+///
+/// The CPU must support the `sha2` feature, see [`is_supported`].
+#[target_feature(enable = "neon,sha2")]
+pub(crate) unsafe fn compress(hash: &mut [u32; 8], blocks: &[u8]) {
+    let mut abcd = vld1q_u32(hash.as_ptr());
+    let mut efgh = vld1q_u32(hash.as_ptr().add(4));
+
+    for block in blocks.chunks_exact(64) {
+        let abcd_initial = abcd;
+        let efgh_initial = efgh;
+
+        // Message words are big-endian, so swap the bytes of each lane.
+        let mut words = [
+            vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(block.as_ptr()))),
+            vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(block.as_ptr().add(16)))),
+            vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(block.as_ptr().add(32)))),
+            vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(block.as_ptr().add(48)))),
+        ];
+
+        for i in 0..16 {
+            let scheduled = vaddq_u32(words[i % 4], vld1q_u32(CBRT_CONST.as_ptr().add(i * 4)));
+            let abcd_previous = abcd;
+            abcd = vsha256hq_u32(abcd_previous, efgh, scheduled);
+            efgh = vsha256h2q_u32(efgh, abcd_previous, scheduled);
+
+            // The last four groups of rounds use words that are already
+            // scheduled.
+            if i < 12 {
+                words[i % 4] = vsha256su1q_u32(
+                    vsha256su0q_u32(words[i % 4], words[(i + 1) % 4]),
+                    words[(i + 2) % 4],
+                    words[(i + 3) % 4],
+                );
+            }
+        }
+
+        abcd = vaddq_u32(abcd, abcd_initial);
+        efgh = vaddq_u32(efgh, efgh_initial);
+    }
+
+    vst1q_u32(hash.as_mut_ptr(), abcd);
+    vst1q_u32(hash.as_mut_ptr().add(4), efgh);
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::compress_portable;
+    use crate::test_util::TestRng;
+
+    #[test]
+    fn test_matches_portable() {
+        if !is_supported() {
+            eprintln!("skipping: CPU lacks the SHA2 extension");
+            return;
+        }
+
+        let mut rng = TestRng(0x9e3779b97f4a7c15);
+        for _ in 0..200 {
+            let mut hash = [0u32; 8];
+            for word in &mut hash {
+                *word = rng.next() as u32;
+            }
+            let mut blocks = vec![0u8; 64 * (rng.next() % 8) as usize];
+            rng.fill(&mut blocks);
+
+            let mut expected = hash;
+            compress_portable(&mut expected, &blocks);
+            let mut actual = hash;
+            // SAFETY: support was checked above.
+            unsafe { compress(&mut actual, &blocks) };
+
+            assert_eq!(actual, expected);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Hashing asynchronous streams, behind the `async` feature. Readers are
+//! `futures_io::AsyncRead`, the trait shared by the `futures` crate and
+//! async-std; the `tokio` feature adds the same support for tokio's
+//! `AsyncRead`.
+
+#![forbid(unsafe_code)]
+
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_io::AsyncRead;
+
+use crate::{Digest, Sha256};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Hashes everything `reader` produces until end of stream.
+pub async fn hash_async_reader<R: AsyncRead + Unpin>(mut reader: R) -> io::Result<Digest> {
+    hash_polled(|cx, buffer| Pin::new(&mut reader).poll_read(cx, buffer)).await
+}
+
+/// Hashes everything a tokio `reader` produces until end of stream.
+#[cfg(feature = "tokio")]
+pub async fn hash_tokio_reader<R: tokio::io::AsyncRead + Unpin>(
+    mut reader: R,
+) -> io::Result<Digest> {
+    hash_polled(|cx, buffer| {
+        let mut buffer = tokio::io::ReadBuf::new(buffer);
+        ready!(Pin::new(&mut reader).poll_read(cx, &mut buffer))?;
+        Poll::Ready(Ok(buffer.filled().len()))
+    })
+    .await
+}
+
+/// Hashes the bytes `poll_read` produces until it reports end of stream.
+async fn hash_polled(
+    mut poll_read: impl FnMut(&mut Context<'_>, &mut [u8]) -> Poll<io::Result<usize>>,
+) -> io::Result<Digest> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+
+    loop {
+        match poll_fn(|cx| poll_read(cx, &mut buffer)).await {
+            Ok(0) => break,
+            Ok(read) => hasher
+                .try_update(&buffer[..read])
+                .map_err(io::Error::other)?,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(hasher.finalize())
+}
+
+/// Hashes everything read through the inner async reader while passing the
+/// data on unchanged. It is a futures `AsyncRead` when the inner reader is
+/// one, and with the `tokio` feature a tokio `AsyncRead` likewise.
+pub struct HashingAsyncReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R> HashingAsyncReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Reading through the returned reference bypasses the hasher.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the inner reader and the digest of everything read so far.
+    pub fn finalize(self) -> (R, Digest) {
+        (self.inner, self.hasher.finalize())
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for HashingAsyncReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buffer: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let read = ready!(Pin::new(&mut this.inner).poll_read(cx, buffer))?;
+        this.hasher
+            .try_update(&buffer[..read])
+            .map_err(io::Error::other)?;
+        Poll::Ready(Ok(read))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for HashingAsyncReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buffer: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let start = buffer.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buffer))?;
+        this.hasher
+            .try_update(&buffer.filled()[start..])
+            .map_err(io::Error::other)?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::tests::random_bytes;
+    use crate::sha256_bytes;
+    use tokio::io::{AsyncWriteExt, DuplexStream};
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    /// A tokio duplex stream fed `contents` in uneven chunks by another task,
+    /// through a pipe small enough that the reader has to wait for it.
+    fn stream(contents: &[u8]) -> DuplexStream {
+        let (mut writer, reader) = tokio::io::duplex(4096);
+        let contents = contents.to_vec();
+        tokio::spawn(async move {
+            for chunk in contents.chunks(1000 + 17) {
+                writer.write_all(chunk).await.unwrap();
+            }
+        });
+        reader
+    }
+
+    #[tokio::test]
+    async fn test_hash_async_reader() {
+        let contents = random_bytes(200_000);
+        let digest = hash_async_reader(stream(&contents).compat()).await.unwrap();
+        assert_eq!(digest, sha256_bytes(&contents));
+
+        let digest = hash_async_reader(&b"abc"[..]).await.unwrap();
+        assert_eq!(digest, sha256_bytes(b"abc"));
+    }
+
+    #[tokio::test]
+    async fn test_hashing_async_reader() {
+        let contents = random_bytes(100_000);
+        let mut reader = HashingAsyncReader::new(stream(&contents).compat());
+
+        let mut forwarded = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            let read = poll_fn(|cx| Pin::new(&mut reader).poll_read(cx, &mut buffer))
+                .await
+                .unwrap();
+            if read == 0 {
+                break;
+            }
+            forwarded.extend_from_slice(&buffer[..read]);
+        }
+
+        assert_eq!(forwarded, contents);
+        assert_eq!(reader.finalize().1, sha256_bytes(&contents));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_tokio_reader() {
+        use tokio::io::AsyncReadExt;
+
+        let contents = random_bytes(150_000);
+        let digest = hash_tokio_reader(stream(&contents)).await.unwrap();
+        assert_eq!(digest, sha256_bytes(&contents));
+
+        let mut reader = HashingAsyncReader::new(stream(&contents));
+        let mut forwarded = Vec::new();
+        reader.read_to_end(&mut forwarded).await.unwrap();
+        assert_eq!(forwarded, contents);
+        assert_eq!(reader.finalize().1, sha256_bytes(&contents));
+    }
+}
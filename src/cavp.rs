@@ -0,0 +1,271 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Runs NIST CAVP response files (`.rsp`) from `tests/cavp` against every
+//! SHA-2 variant. The files are NIST's byte-oriented SHAVS examples,
+//! unmodified and named as in the download, for example `SHA256ShortMsg.rsp`,
+//! `SHA256LongMsg.rsp` and `SHA256Monte.rsp`. A missing or malformed file
+//! fails the test. SHA-256 records are also hashed with `sha256_bits`, and
+//! the bit-oriented SHA-256 files go in `tests/cavp/bit`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::test_util::from_hex;
+use crate::{
+    sha224_bytes, sha256_bits, sha256_bytes, sha384_bytes, sha512_224_bytes, sha512_256_bytes,
+    sha512_bytes,
+};
+
+type HashFn = fn(&[u8]) -> Vec<u8>;
+type HashBitsFn = fn(&[u8], u64) -> Vec<u8>;
+
+struct Algorithm {
+    /// File name prefix, such as `SHA256`.
+    name: &'static str,
+    hash: HashFn,
+    /// Hashes messages that aren't a whole number of bytes, for the
+    /// bit-oriented files.
+    hash_bits: Option<HashBitsFn>,
+}
+
+const ALGORITHMS: [Algorithm; 6] = [
+    Algorithm {
+        name: "SHA224",
+        hash: |message| sha224_bytes(message).as_bytes().to_vec(),
+        hash_bits: None,
+    },
+    Algorithm {
+        name: "SHA256",
+        hash: |message| sha256_bytes(message).as_bytes().to_vec(),
+        hash_bits: Some(|message, bit_length| {
+            sha256_bits(message, bit_length)
+                .unwrap()
+                .as_bytes()
+                .to_vec()
+        }),
+    },
+    Algorithm {
+        name: "SHA384",
+        hash: |message| sha384_bytes(message).as_bytes().to_vec(),
+        hash_bits: None,
+    },
+    Algorithm {
+        name: "SHA512",
+        hash: |message| sha512_bytes(message).as_bytes().to_vec(),
+        hash_bits: None,
+    },
+    Algorithm {
+        name: "SHA512_224",
+        hash: |message| sha512_224_bytes(message).as_bytes().to_vec(),
+        hash_bits: None,
+    },
+    Algorithm {
+        name: "SHA512_256",
+        hash: |message| sha512_256_bytes(message).as_bytes().to_vec(),
+        hash_bits: None,
+    },
+];
+
+/// The `key = value` lines of one blank-line separated record.
+type Record = HashMap<String, String>;
+
+/// Splits a response file into records. Comments and `[...]` section headers
+/// are skipped, since the digest length they announce is implied by the
+/// algorithm. Any other line that isn't `key = value` is an error naming its
+/// line number.
+fn parse(contents: &str) -> Result<Vec<Record>, String> {
+    let mut records = Vec::new();
+    let mut record = Record::new();
+
+    for (number, line) in contents.lines().map(str::trim).enumerate() {
+        if line.is_empty() {
+            if !record.is_empty() {
+                records.push(std::mem::take(&mut record));
+            }
+            continue;
+        }
+        if line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected `key = value`", number + 1));
+        };
+        record.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    if !record.is_empty() {
+        records.push(record);
+    }
+
+    if records.is_empty() {
+        return Err("no records".to_string());
+    }
+    Ok(records)
+}
+
+/// Reads and parses `tests/cavp/{file_name}`, panicking if the file is
+/// missing or malformed so a lost vector file can't pass silently.
+fn load(file_name: &str) -> Vec<Record> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/cavp")
+        .join(file_name);
+    let contents =
+        fs::read_to_string(&path).unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+    parse(&contents).unwrap_or_else(|error| panic!("{}: {}", path.display(), error))
+}
+
+/// Checks every `Len`/`Msg`/`MD` record of a ShortMsg or LongMsg file,
+/// returning how many were run.
+fn check_messages(algorithm: &Algorithm, records: &[Record]) -> usize {
+    let mut checked = 0;
+    for record in records {
+        let (Some(length), Some(message), Some(expected)) =
+            (record.get("Len"), record.get("Msg"), record.get("MD"))
+        else {
+            panic!(
+                "{}: record without Len, Msg and MD: {:?}",
+                algorithm.name, record
+            );
+        };
+        let bit_length: u64 = length.parse().unwrap();
+        let message = from_hex(message);
+        let expected = from_hex(expected);
+
+        if bit_length.is_multiple_of(8) {
+            // An empty message is written as `Msg = 00`.
+            let actual = (algorithm.hash)(&message[..(bit_length / 8) as usize]);
+            assert_eq!(actual, expected, "{} Len = {}", algorithm.name, bit_length);
+        }
+        // The bit-granular path has to agree on whole bytes too.
+        if let Some(hash_bits) = algorithm.hash_bits {
+            let actual = hash_bits(&message, bit_length);
+            assert_eq!(
+                actual, expected,
+                "{} Len = {} (bits)",
+                algorithm.name, bit_length
+            );
+        } else if !bit_length.is_multiple_of(8) {
+            continue;
+        }
+        checked += 1;
+    }
+    checked
+}
+
+/// Runs the SHAVS Monte Carlo procedure for SHA-2. Starting from the seed,
+/// each of the 100 checkpoints is the last of 1000 digests, where every
+/// message is the previous three digests concatenated. The next checkpoint
+/// restarts from that digest.
+fn check_monte_carlo(algorithm: &Algorithm, records: &[Record]) -> usize {
+    let seed = records
+        .iter()
+        .find_map(|record| record.get("Seed"))
+        .expect("Monte Carlo file has no seed");
+    let mut checkpoint = from_hex(seed);
+
+    let mut checked = 0;
+    for record in records {
+        let (Some(count), Some(expected)) = (record.get("COUNT"), record.get("MD")) else {
+            continue;
+        };
+
+        let mut digests = [checkpoint.clone(), checkpoint.clone(), checkpoint];
+        for _ in 0..1000 {
+            let message = digests.concat();
+            digests.rotate_left(1);
+            digests[2] = (algorithm.hash)(&message);
+        }
+        let [_, _, last] = digests;
+
+        assert_eq!(
+            last,
+            from_hex(expected),
+            "{} COUNT = {}",
+            algorithm.name,
+            count
+        );
+        checkpoint = last;
+        checked += 1;
+    }
+    checked
+}
+
+#[test]
+fn test_short_messages() {
+    for algorithm in &ALGORITHMS {
+        let records = load(&format!("{}ShortMsg.rsp", algorithm.name));
+        assert_eq!(check_messages(algorithm, &records), records.len());
+    }
+}
+
+#[test]
+fn test_long_messages() {
+    for algorithm in &ALGORITHMS {
+        let records = load(&format!("{}LongMsg.rsp", algorithm.name));
+        assert_eq!(check_messages(algorithm, &records), records.len());
+    }
+}
+
+/// NIST's bit-oriented SHA-256 files, from `shabittestvectors.zip`, run
+/// through `sha256_bits`. Most of their lengths aren't whole bytes.
+#[test]
+fn test_bit_oriented_messages() {
+    let algorithm = &ALGORITHMS[1];
+    for file_name in ["bit/SHA256ShortMsg.rsp", "bit/SHA256LongMsg.rsp"] {
+        let records = load(file_name);
+        let partial_bytes = records
+            .iter()
+            .filter(|record| {
+                record
+                    .get("Len")
+                    .is_some_and(|length| !length.parse::<u64>().unwrap().is_multiple_of(8))
+            })
+            .count();
+        assert!(
+            partial_bytes > 0,
+            "{} has no bit-oriented records",
+            file_name
+        );
+        assert_eq!(check_messages(algorithm, &records), records.len());
+    }
+}
+
+#[test]
+fn test_monte_carlo() {
+    for algorithm in &ALGORITHMS {
+        let records = load(&format!("{}Monte.rsp", algorithm.name));
+        assert_eq!(check_monte_carlo(algorithm, &records), 100);
+    }
+}
+
+#[test]
+fn test_parse() {
+    let records = parse(
+        "#  CAVS 11.0\r\n\
+         [L = 32]\r\n\
+         \r\n\
+         Len = 8\r\n\
+         Msg = d3\r\n\
+         MD = 28969cdfa74a12c82f3bad960b0b000aca2ac329deea5c2328ebc6f2ba9802c1\r\n",
+    )
+    .unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["Len"], "8");
+    assert_eq!(records[0]["Msg"], "d3");
+    assert_eq!(check_messages(&ALGORITHMS[1], &records), 1);
+
+    assert_eq!(
+        parse("Len = 8\nMsg d3\n"),
+        Err("line 2: expected `key = value`".to_string())
+    );
+    assert_eq!(parse("#  CAVS 11.0\n"), Err("no records".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "Missing.rsp")]
+fn test_missing_file() {
+    load("Missing.rsp");
+}
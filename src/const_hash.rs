@@ -0,0 +1,140 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! SHA-256 as a `const fn`, so digests of embedded data can be computed at
+//! compile time.
+
+#![forbid(unsafe_code)]
+
+use crate::{create_message_schedule, do_compression, SQRT_CONST};
+
+/// Hashes `input` with SHA-256. Being a `const fn`, it can initialise
+/// constants and statics, but it always uses the portable rounds, so prefer
+/// [`sha256_bytes`](crate::sha256_bytes) at runtime.
+///
+/// ```
+/// const DIGEST: [u8; 32] = sha256_rust::sha256_const(b"abc");
+/// assert_eq!(&DIGEST, sha256_rust::sha256_bytes(b"abc").as_bytes());
+/// ```
+pub const fn sha256_const(input: &[u8]) -> [u8; 32] {
+    let mut hash = SQRT_CONST;
+    let mut block = [0u8; 64];
+
+    let full_blocks = input.len() / 64;
+    let mut i = 0;
+    while i < full_blocks {
+        copy_into(&mut block, input, i * 64, 64);
+        hash = do_compression(hash, &create_message_schedule(&block));
+        i += 1;
+    }
+
+    // A single 0x80 byte, then zeros and the big-endian bit length, spilling
+    // into a second block when fewer than 9 bytes are left.
+    let remaining = input.len() % 64;
+    block = [0; 64];
+    copy_into(&mut block, input, full_blocks * 64, remaining);
+    block[remaining] = 0x80;
+    if remaining >= 56 {
+        hash = do_compression(hash, &create_message_schedule(&block));
+        block = [0; 64];
+    }
+
+    let bit_length = (input.len() as u64).wrapping_mul(8).to_be_bytes();
+    let mut i = 0;
+    while i < 8 {
+        block[56 + i] = bit_length[i];
+        i += 1;
+    }
+    hash = do_compression(hash, &create_message_schedule(&block));
+
+    let mut digest = [0u8; 32];
+    let mut i = 0;
+    while i < 8 {
+        let word = hash[i].to_be_bytes();
+        digest[i * 4] = word[0];
+        digest[i * 4 + 1] = word[1];
+        digest[i * 4 + 2] = word[2];
+        digest[i * 4 + 3] = word[3];
+        i += 1;
+    }
+    digest
+}
+
+/// Copies `length` bytes of `source` starting at `offset` to the front of
+/// `block`.
+const fn copy_into(block: &mut [u8; 64], source: &[u8], offset: usize, length: usize) {
+    let mut i = 0;
+    while i < length {
+        block[i] = source[offset + i];
+        i += 1;
+    }
+}
+
+/// Hashes a file's contents at compile time, evaluating to its SHA-256 digest
+/// as a `[u8; 32]`. The path is resolved like [`include_bytes!`], relative to
+/// the file the macro is used in.
+///
+/// Compile-time evaluation hashes only a few kilobytes per second of build
+/// time, so this suits small assets such as schemas. The
+/// `long_running_const_eval` lint is allowed so larger files still build.
+///
+/// ```ignore
+/// const SCHEMA_DIGEST: [u8; 32] = sha256_rust::include_sha256!("schema.json");
+/// ```
+#[macro_export]
+macro_rules! include_sha256 {
+    ($path:expr) => {{
+        #[allow(long_running_const_eval)]
+        const DIGEST: [u8; 32] = $crate::sha256_const(include_bytes!($path));
+        DIGEST
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sha256_bytes;
+    use crate::test_util::TestRng;
+
+    const EMPTY: [u8; 32] = sha256_const(b"");
+    const ABC: [u8; 32] = sha256_const(b"abc");
+    const TWO_BLOCK: [u8; 32] =
+        sha256_const(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq");
+
+    #[test]
+    fn test_const_matches_runtime() {
+        assert_eq!(&EMPTY, sha256_bytes(b"").as_bytes());
+        assert_eq!(&ABC, sha256_bytes(b"abc").as_bytes());
+        assert_eq!(
+            &TWO_BLOCK,
+            sha256_bytes(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq").as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_padding_boundaries() {
+        let mut rng = TestRng(0x2545f4914f6cdd1d);
+        let mut input = [0u8; 300];
+        rng.fill(&mut input);
+
+        for length in 0..input.len() {
+            assert_eq!(
+                &sha256_const(&input[..length]),
+                sha256_bytes(&input[..length]).as_bytes(),
+                "length {}",
+                length
+            );
+        }
+    }
+
+    #[test]
+    fn test_include_sha256() {
+        const README: [u8; 32] = include_sha256!("../README.md");
+        assert_eq!(
+            &README,
+            sha256_bytes(include_bytes!("../README.md")).as_bytes()
+        );
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+#![forbid(unsafe_code)]
+
+#[cfg(feature = "alloc")]
+use alloc::{format, string::String};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::hint::black_box;
+use core::str::FromStr;
+
+/// An `N`-byte hash output, 32 bytes for SHA-256. Equality is checked in
+/// constant time so digests can be compared against secret values.
+#[derive(Clone, Copy)]
+pub struct Digest<const N: usize = 32>(pub(crate) [u8; N]);
+
+impl<const N: usize> Digest<N> {
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn to_hex(&self) -> String {
+        format!("{:x}", self)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn to_hex_upper(&self) -> String {
+        format!("{:X}", self)
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Digest<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> From<Digest<N>> for [u8; N] {
+    fn from(digest: Digest<N>) -> Self {
+        digest.0
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for Digest<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> PartialEq for Digest<N> {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.0, &other.0)
+    }
+}
+
+impl<const N: usize> Eq for Digest<N> {}
+
+impl<const N: usize> PartialOrd for Digest<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for Digest<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<const N: usize> Hash for Digest<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<const N: usize> fmt::LowerHex for Digest<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> fmt::UpperHex for Digest<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> fmt::Display for Digest<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl<const N: usize> fmt::Debug for Digest<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Digest({:x})", self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseDigestError {
+    /// The input was not exactly two hex characters per digest byte.
+    InvalidLength(usize),
+    /// A character at the given byte offset was not a hex digit.
+    InvalidCharacter(usize),
+}
+
+impl fmt::Display for ParseDigestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength(length) => {
+                write!(f, "invalid digest length of {} hex characters", length)
+            }
+            Self::InvalidCharacter(index) => {
+                write!(f, "invalid hex character at position {}", index)
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParseDigestError {}
+
+impl<const N: usize> FromStr for Digest<N> {
+    type Err = ParseDigestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.as_bytes();
+        if hex.len() != N * 2 {
+            return Err(ParseDigestError::InvalidLength(hex.len()));
+        }
+
+        let mut bytes = [0u8; N];
+        decode_hex(hex, &mut bytes).map_err(ParseDigestError::InvalidCharacter)?;
+        Ok(Self(bytes))
+    }
+}
+
+/// Decodes `hex`, which must be exactly twice as long as `bytes`, into
+/// `bytes`. Fails with the offset of the first character that isn't a hex
+/// digit.
+pub(crate) fn decode_hex(hex: &[u8], bytes: &mut [u8]) -> Result<(), usize> {
+    debug_assert_eq!(hex.len(), bytes.len() * 2);
+    for (i, (byte, pair)) in bytes.iter_mut().zip(hex.chunks_exact(2)).enumerate() {
+        let high = hex_value(pair[0]).ok_or(i * 2)?;
+        let low = hex_value(pair[1]).ok_or(i * 2 + 1)?;
+        *byte = high << 4 | low;
+    }
+    Ok(())
+}
+
+fn hex_value(character: u8) -> Option<u8> {
+    match character {
+        b'0'..=b'9' => Some(character - b'0'),
+        b'a'..=b'f' => Some(character - b'a' + 10),
+        b'A'..=b'F' => Some(character - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Compares two byte slices without short-circuiting on the first mismatch.
+/// Only the lengths are allowed to leak.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut difference = 0u8;
+    for (x, y) in a.iter().zip(b) {
+        difference |= x ^ y;
+    }
+    black_box(difference) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use crate::sha256_bytes;
+    #[cfg(feature = "alloc")]
+    use alloc::{format, string::ToString};
+
+    #[cfg(feature = "alloc")]
+    const EMPTY: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_formatting() {
+        let digest = sha256_bytes(b"");
+        assert_eq!(digest.to_hex(), EMPTY);
+        assert_eq!(digest.to_hex_upper(), EMPTY.to_uppercase());
+        assert_eq!(digest.to_string(), EMPTY);
+        assert_eq!(format!("{:X}", digest), EMPTY.to_uppercase());
+        assert_eq!(digest.as_bytes()[..4], [0xe3, 0xb0, 0xc4, 0x42]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_parse() {
+        let digest = sha256_bytes(b"");
+        assert_eq!(EMPTY.parse::<Digest>(), Ok(digest));
+        assert_eq!(EMPTY.to_uppercase().parse::<Digest>(), Ok(digest));
+        assert_eq!(
+            "abc".parse::<Digest>(),
+            Err(ParseDigestError::InvalidLength(3))
+        );
+        assert_eq!(
+            EMPTY.replace('e', "g").parse::<Digest>(),
+            Err(ParseDigestError::InvalidCharacter(0))
+        );
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        let mut bytes = [0u8; 3];
+        assert_eq!(decode_hex(b"00aBfF", &mut bytes), Ok(()));
+        assert_eq!(bytes, [0x00, 0xab, 0xff]);
+        assert_eq!(decode_hex(b"00a-ff", &mut bytes), Err(3));
+        assert_eq!(decode_hex(b"00ab f", &mut bytes), Err(4));
+    }
+
+    #[test]
+    fn test_ordering() {
+        let low = Digest([0; 32]);
+        let mut high = [0; 32];
+        high[31] = 1;
+        let high = Digest(high);
+
+        assert!(low < high);
+        assert_ne!(low, high);
+        assert_eq!(low, Digest([0; 32]));
+    }
+}
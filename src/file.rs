@@ -0,0 +1,196 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Hashing readers and files, including batches of files across threads.
+
+#![forbid(unsafe_code)]
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::{Digest, Sha256};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Hashes everything `reader` produces until end of file.
+pub fn hash_reader(reader: impl Read) -> io::Result<Digest> {
+    hash_reader_with_progress(reader, |_| {})
+}
+
+/// Like [`hash_reader`], calling `progress` with the total number of bytes
+/// hashed so far after each read.
+pub fn hash_reader_with_progress(
+    mut reader: impl Read,
+    mut progress: impl FnMut(u64),
+) -> io::Result<Digest> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut processed = 0u64;
+
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => {
+                hasher
+                    .try_update(&buffer[..read])
+                    .map_err(io::Error::other)?;
+                processed += read as u64;
+                progress(processed);
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(hasher.finalize())
+}
+
+/// Hashes the file at `path`, reading it in buffered chunks.
+pub fn hash_file(path: impl AsRef<Path>) -> io::Result<Digest> {
+    hash_file_with_progress(path, |_| {})
+}
+
+/// Like [`hash_file`], calling `progress` with the total number of bytes
+/// hashed so far as the file is processed.
+pub fn hash_file_with_progress(
+    path: impl AsRef<Path>,
+    progress: impl FnMut(u64),
+) -> io::Result<Digest> {
+    hash_reader_with_progress(File::open(path)?, progress)
+}
+
+/// Hashes each file in `paths` with [`hash_file`], spreading them over one
+/// thread per available CPU. Results are in the same order as `paths`.
+pub fn hash_files<P: AsRef<Path> + Sync>(paths: &[P]) -> Vec<io::Result<Digest>> {
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(paths.len());
+    let next = AtomicUsize::new(0);
+
+    let mut indexed: Vec<(usize, io::Result<Digest>)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(index) else {
+                            break;
+                        };
+                        results.push((index, hash_file(path)));
+                    }
+                    results
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| match handle.join() {
+                Ok(results) => results,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    });
+
+    indexed.sort_unstable_by_key(|(index, _)| *index);
+    indexed.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::sha256_bytes;
+    use crate::test_util::TestRng;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// A file in the system temp directory, removed on drop.
+    pub(crate) struct TempFile(pub(crate) PathBuf);
+
+    impl TempFile {
+        pub(crate) fn new(name: &str, contents: &[u8]) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("sha256-rust-{}-{}", std::process::id(), name));
+            fs::write(&path, contents).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    pub(crate) fn random_bytes(length: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; length];
+        TestRng(0x9e3779b97f4a7c15).fill(&mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn test_hash_reader() {
+        let contents = random_bytes(3 * BUFFER_SIZE + 17);
+        let mut reports = Vec::new();
+        let digest = hash_reader_with_progress(&contents[..], |total| reports.push(total)).unwrap();
+
+        assert_eq!(digest, sha256_bytes(&contents));
+        assert_eq!(reports.last(), Some(&(contents.len() as u64)));
+        assert!(reports.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let small = random_bytes(1000);
+        let file = TempFile::new("small", &small);
+        assert_eq!(hash_file(&file.0).unwrap(), sha256_bytes(&small));
+
+        let empty = TempFile::new("empty", b"");
+        assert_eq!(hash_file(&empty.0).unwrap(), sha256_bytes(b""));
+
+        assert_eq!(
+            hash_file("/nonexistent/sha256-rust").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn test_hash_large_file() {
+        let contents = random_bytes(40 * BUFFER_SIZE + 3);
+        let file = TempFile::new("large", &contents);
+
+        let mut last = 0;
+        let digest = hash_file_with_progress(&file.0, |total| last = total).unwrap();
+        assert_eq!(digest, sha256_bytes(&contents));
+        assert_eq!(last, contents.len() as u64);
+    }
+
+    #[test]
+    fn test_hash_files() {
+        let files: Vec<TempFile> = (0..20)
+            .map(|i| TempFile::new(&format!("batch-{}", i), &random_bytes(i * 1000)))
+            .collect();
+        let mut paths: Vec<PathBuf> = files.iter().map(|file| file.0.clone()).collect();
+        paths.insert(5, PathBuf::from("/nonexistent/sha256-rust"));
+
+        let results = hash_files(&paths);
+        assert_eq!(results.len(), paths.len());
+        for (i, result) in results.iter().enumerate() {
+            match i {
+                5 => assert!(result.is_err()),
+                _ => {
+                    let size = if i < 5 { i } else { i - 1 } * 1000;
+                    assert_eq!(*result.as_ref().unwrap(), sha256_bytes(&random_bytes(size)));
+                }
+            }
+        }
+        assert!(hash_files::<PathBuf>(&[]).is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! HKDF-SHA256 key derivation as specified in RFC 5869.
+
+#![forbid(unsafe_code)]
+
+use core::fmt;
+
+use crate::{Digest, Hmac};
+
+const HASH_LENGTH: usize = 32;
+
+/// The most output HKDF-SHA256 can produce, 255 blocks of 32 bytes.
+pub const HKDF_MAX_OUTPUT_LENGTH: usize = 255 * HASH_LENGTH;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HkdfError {
+    /// The requested output length was more than [`HKDF_MAX_OUTPUT_LENGTH`].
+    OutputTooLong(usize),
+}
+
+impl fmt::Display for HkdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutputTooLong(length) => write!(
+                f,
+                "requested {} bytes of output, at most {} are allowed",
+                length, HKDF_MAX_OUTPUT_LENGTH
+            ),
+        }
+    }
+}
+
+impl core::error::Error for HkdfError {}
+
+/// Derives a pseudorandom key from input keying material. An empty salt is
+/// treated as 32 zero bytes.
+pub fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> Digest {
+    let mut hmac = if salt.is_empty() {
+        Hmac::new(&[0; HASH_LENGTH])
+    } else {
+        Hmac::new(salt)
+    };
+    hmac.update(ikm);
+    hmac.finalize()
+}
+
+/// Fills `okm` with output keying material derived from `prk` and `info`.
+pub fn hkdf_expand(prk: &[u8], info: &[u8], okm: &mut [u8]) -> Result<(), HkdfError> {
+    if okm.len() > HKDF_MAX_OUTPUT_LENGTH {
+        return Err(HkdfError::OutputTooLong(okm.len()));
+    }
+
+    let keyed = Hmac::new(prk);
+    let mut previous: Option<Digest> = None;
+
+    for (counter, chunk) in (1..=255u8).zip(okm.chunks_mut(HASH_LENGTH)) {
+        let mut hmac = keyed.clone();
+        if let Some(previous) = &previous {
+            hmac.update(previous.as_bytes());
+        }
+        hmac.update(info);
+        hmac.update(&[counter]);
+
+        let block = hmac.finalize();
+        chunk.copy_from_slice(&block.as_bytes()[..chunk.len()]);
+        previous = Some(block);
+    }
+
+    Ok(())
+}
+
+/// Runs extract followed by expand, filling `okm`.
+pub fn hkdf(salt: &[u8], ikm: &[u8], info: &[u8], okm: &mut [u8]) -> Result<(), HkdfError> {
+    let prk = hkdf_extract(salt, ikm);
+    hkdf_expand(prk.as_bytes(), info, okm)
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::test_util::from_hex;
+    use alloc::{vec, vec::Vec};
+
+    #[test]
+    fn test_rfc5869_basic() {
+        let ikm = [0x0b; 22];
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+
+        let prk = hkdf_extract(&salt, &ikm);
+        assert_eq!(
+            prk.to_hex(),
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"
+        );
+
+        let mut okm = [0u8; 42];
+        hkdf_expand(prk.as_bytes(), &info, &mut okm).unwrap();
+        let expected =
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865";
+        assert_eq!(okm.to_vec(), from_hex(expected));
+    }
+
+    #[test]
+    fn test_rfc5869_long_inputs() {
+        let ikm: Vec<u8> = (0x00..=0x4f).collect();
+        let salt: Vec<u8> = (0x60..=0xaf).collect();
+        let info: Vec<u8> = (0xb0..=0xff).collect();
+
+        let prk = hkdf_extract(&salt, &ikm);
+        assert_eq!(
+            prk.to_hex(),
+            "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244"
+        );
+
+        let mut okm = [0u8; 82];
+        hkdf(&salt, &ikm, &info, &mut okm).unwrap();
+        let expected = "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c\
+                        59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71\
+                        cc30c58179ec3e87c14c01d5c1f3434f1d87";
+        assert_eq!(okm.to_vec(), from_hex(expected));
+    }
+
+    #[test]
+    fn test_rfc5869_empty_salt_and_info() {
+        let ikm = [0x0b; 22];
+
+        let prk = hkdf_extract(&[], &ikm);
+        assert_eq!(
+            prk.to_hex(),
+            "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04"
+        );
+
+        let mut okm = [0u8; 42];
+        hkdf(&[], &ikm, &[], &mut okm).unwrap();
+        let expected =
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8";
+        assert_eq!(okm.to_vec(), from_hex(expected));
+    }
+
+    #[test]
+    fn test_output_length_limit() {
+        let prk = hkdf_extract(b"salt", b"ikm");
+
+        let mut okm = vec![0u8; HKDF_MAX_OUTPUT_LENGTH];
+        assert_eq!(hkdf_expand(prk.as_bytes(), b"", &mut okm), Ok(()));
+
+        let mut okm = vec![0u8; HKDF_MAX_OUTPUT_LENGTH + 1];
+        assert_eq!(
+            hkdf_expand(prk.as_bytes(), b"", &mut okm),
+            Err(HkdfError::OutputTooLong(HKDF_MAX_OUTPUT_LENGTH + 1))
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! HMAC-SHA256 as specified in RFC 2104.
+
+#![forbid(unsafe_code)]
+
+use crate::digest::constant_time_eq;
+use crate::{sha256_bytes, Digest, Sha256};
+
+const BLOCK_SIZE: usize = 64;
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Digest {
+    let mut hmac = Hmac::new(key);
+    hmac.update(message);
+    hmac.finalize()
+}
+
+/// Checks `tag` against the HMAC of `message` without leaking where the two
+/// differ.
+pub fn verify_hmac_sha256(key: &[u8], message: &[u8], tag: &[u8]) -> bool {
+    let mut hmac = Hmac::new(key);
+    hmac.update(message);
+    hmac.verify(tag)
+}
+
+/// Streaming HMAC-SHA256 state. The keyed inner and outer hash states are
+/// computed once in `new`, so cloning an `Hmac` is a cheap way to reuse a key.
+#[derive(Clone)]
+pub struct Hmac {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl Hmac {
+    pub fn new(key: &[u8]) -> Self {
+        // Keys longer than a block are hashed first, shorter ones are
+        // zero-padded to the block size.
+        let mut block_key = [0u8; BLOCK_SIZE];
+        if key.len() > BLOCK_SIZE {
+            block_key[..32].copy_from_slice(sha256_bytes(key).as_bytes());
+        } else {
+            block_key[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = Sha256::new();
+        let mut outer = Sha256::new();
+        inner.update(&block_key.map(|byte| byte ^ IPAD));
+        outer.update(&block_key.map(|byte| byte ^ OPAD));
+
+        Self { inner, outer }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.inner.update(bytes);
+    }
+
+    pub fn finalize(self) -> Digest {
+        let mut outer = self.outer;
+        outer.update(self.inner.finalize().as_bytes());
+        outer.finalize()
+    }
+
+    /// Returns whether `tag` matches, comparing in constant time.
+    pub fn verify(self, tag: &[u8]) -> bool {
+        constant_time_eq(self.finalize().as_bytes(), tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_rfc4231() {
+        assert_eq!(
+            hmac_sha256(&[0x0b; 20], b"Hi There").to_hex(),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            hmac_sha256(b"Jefe", b"what do ya want for nothing?").to_hex(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hmac_sha256(&[0xaa; 20], &[0xdd; 50]).to_hex(),
+            "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe"
+        );
+
+        let key: Vec<u8> = (1..=25).collect();
+        assert_eq!(
+            hmac_sha256(&key, &[0xcd; 50]).to_hex(),
+            "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b"
+        );
+
+        let truncated = hmac_sha256(&[0x0c; 20], b"Test With Truncation").to_hex();
+        assert_eq!(&truncated[..32], "a3b6167473100ee06e0c796c2955552b");
+
+        assert_eq!(
+            hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )
+            .to_hex(),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+        assert_eq!(
+            hmac_sha256(
+                &[0xaa; 131],
+                b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm."
+            ).to_hex(),
+            "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2"
+        );
+    }
+
+    #[test]
+    fn test_streaming() {
+        let message = b"what do ya want for nothing?";
+        let mut hmac = Hmac::new(b"Jefe");
+        for chunk in message.chunks(5) {
+            hmac.update(chunk);
+        }
+        assert_eq!(hmac.finalize(), hmac_sha256(b"Jefe", message));
+    }
+
+    #[test]
+    fn test_verify() {
+        let tag = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert!(verify_hmac_sha256(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            tag.as_bytes()
+        ));
+
+        let mut forged = *tag.as_bytes();
+        forged[31] ^= 1;
+        assert!(!verify_hmac_sha256(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            &forged
+        ));
+        assert!(!verify_hmac_sha256(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            &tag.as_bytes()[..16]
+        ));
+    }
+}
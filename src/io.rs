@@ -0,0 +1,416 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! `std::io` and `std::hash` integration: hashers as `Write` sinks, reader and
+//! writer adapters that hash what passes through them, and a keyed
+//! `BuildHasher` for hash maps.
+
+#![forbid(unsafe_code)]
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, Read, Write};
+
+use crate::{Digest, Sha224, Sha256};
+
+impl Write for Sha256 {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.try_update(bytes).map_err(io::Error::other)?;
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Write for Sha224 {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.try_update(bytes).map_err(io::Error::other)?;
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hashes everything read through the inner reader.
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    /// Length of the buffer last returned by `fill_buf`, so `consume` knows
+    /// how much of it is still buffered in the inner reader.
+    filled: usize,
+    /// An error from `consume`, which can't return one, reported by the next
+    /// read.
+    error: Option<io::Error>,
+}
+
+impl<R> HashingReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            filled: 0,
+            error: None,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Reading through the returned reference bypasses the hasher.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.filled = 0;
+        &mut self.inner
+    }
+
+    /// Returns the inner reader and the digest of everything read so far.
+    pub fn finalize(self) -> (R, Digest) {
+        (self.inner, self.hasher.finalize())
+    }
+
+    fn take_error(&mut self) -> io::Result<()> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.take_error()?;
+        self.filled = 0;
+        let read = self.inner.read(buffer)?;
+        self.hasher
+            .try_update(&buffer[..read])
+            .map_err(io::Error::other)?;
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for HashingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.take_error()?;
+        let buffered = self.inner.fill_buf()?;
+        self.filled = buffered.len();
+        Ok(buffered)
+    }
+
+    /// Hashes the consumed bytes, which are still at the front of the inner
+    /// reader's buffer. Since that buffer isn't empty, `fill_buf` returns it
+    /// again without reading; consuming nothing doesn't touch it at all.
+    fn consume(&mut self, amount: usize) {
+        let hashed = amount.min(self.filled);
+        if hashed > 0 {
+            let result = match self.inner.fill_buf() {
+                Ok(buffered) if buffered.len() >= hashed => self
+                    .hasher
+                    .try_update(&buffered[..hashed])
+                    .map_err(io::Error::other),
+                Ok(_) => Err(io::Error::other("inner reader's buffer shrank")),
+                Err(error) => Err(error),
+            };
+            if let Err(error) = result {
+                self.error.get_or_insert(error);
+            }
+        }
+        self.filled -= hashed;
+        self.inner.consume(amount);
+    }
+}
+
+/// Hashes everything written through to the inner writer. Only bytes the
+/// inner writer accepts are hashed, so short writes are accounted for.
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Writing through the returned reference bypasses the hasher.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the inner writer and the digest of everything written so far.
+    pub fn finalize(self) -> (W, Digest) {
+        (self.inner, self.hasher.finalize())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(bytes)?;
+        self.hasher
+            .try_update(&bytes[..written])
+            .map_err(io::Error::other)?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Builds [`Sha256Hasher`]s keyed with a secret, so hash map keys chosen by
+/// an attacker can't be made to collide. Slower than the default SipHash, but
+/// useful where a cryptographic hash is required.
+#[derive(Clone)]
+pub struct Sha256BuildHasher {
+    /// SHA-256 state after absorbing the key block.
+    keyed: Sha256,
+}
+
+impl Sha256BuildHasher {
+    /// Uses a random key, drawn from the same source as the standard library's
+    /// `RandomState`.
+    pub fn new() -> Self {
+        let random = RandomState::new();
+        let mut key = [0u8; 32];
+        for (index, chunk) in key.chunks_exact_mut(8).enumerate() {
+            chunk.copy_from_slice(&random.hash_one(index).to_le_bytes());
+        }
+        Self::with_key(&key)
+    }
+
+    /// Uses a fixed key, so hashes are reproducible across processes.
+    pub fn with_key(key: &[u8; 32]) -> Self {
+        // The key fills a whole block, so each hasher starts from a
+        // precomputed state instead of rehashing it.
+        let mut block = [0u8; 64];
+        block[..32].copy_from_slice(key);
+        let mut keyed = Sha256::new();
+        keyed.update(&block);
+        Self { keyed }
+    }
+}
+
+impl Default for Sha256BuildHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuildHasher for Sha256BuildHasher {
+    type Hasher = Sha256Hasher;
+
+    fn build_hasher(&self) -> Sha256Hasher {
+        Sha256Hasher {
+            inner: self.keyed.clone(),
+        }
+    }
+}
+
+/// A [`Hasher`] whose output is the first 8 bytes of a keyed SHA-256 digest.
+#[derive(Clone)]
+pub struct Sha256Hasher {
+    inner: Sha256,
+}
+
+impl Hasher for Sha256Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.inner.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.inner.clone().finalize();
+        let mut first = [0u8; 8];
+        first.copy_from_slice(&digest.as_bytes()[..8]);
+        u64::from_be_bytes(first)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sha224_bytes, sha256_bytes};
+    use std::collections::HashMap;
+
+    const TEXT: &[u8] = b"The quick brown fox jumps over the lazy dog";
+
+    /// Accepts at most three bytes per write.
+    struct ShortWriter(Vec<u8>);
+
+    impl Write for ShortWriter {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            let accepted = bytes.len().min(3);
+            self.0.extend_from_slice(&bytes[..accepted]);
+            Ok(accepted)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_copy_into_hasher() {
+        let mut hasher = Sha256::new();
+        io::copy(&mut &TEXT[..], &mut hasher).unwrap();
+        assert_eq!(hasher.finalize(), sha256_bytes(TEXT));
+
+        let mut hasher = Sha224::new();
+        hasher.write_all(TEXT).unwrap();
+        assert_eq!(hasher.finalize(), sha224_bytes(TEXT));
+    }
+
+    #[test]
+    fn test_hashing_reader() {
+        let mut reader = HashingReader::new(TEXT);
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+
+        let (rest, digest) = reader.finalize();
+        assert_eq!(output, TEXT);
+        assert!(rest.is_empty());
+        assert_eq!(digest, sha256_bytes(TEXT));
+    }
+
+    #[test]
+    fn test_hashing_reader_buffered() {
+        let mut reader = HashingReader::new(io::BufReader::with_capacity(4, TEXT));
+        let mut first_line = Vec::new();
+        reader.read_until(b' ', &mut first_line).unwrap();
+        assert_eq!(first_line, b"The ");
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(reader.finalize().1, sha256_bytes(TEXT));
+    }
+
+    /// Serves `chunks` one `fill_buf` refill at a time, counting refills,
+    /// and fails the next `fill_buf` once `fail` is set.
+    struct ChunkReader {
+        chunks: Vec<&'static [u8]>,
+        buffer: &'static [u8],
+        refills: usize,
+        fail: bool,
+    }
+
+    impl ChunkReader {
+        fn new(chunks: &[&'static [u8]]) -> Self {
+            Self {
+                chunks: chunks.iter().rev().copied().collect(),
+                buffer: &[],
+                refills: 0,
+                fail: false,
+            }
+        }
+    }
+
+    impl Read for ChunkReader {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let read = self.fill_buf()?.read(buffer)?;
+            self.consume(read);
+            Ok(read)
+        }
+    }
+
+    impl BufRead for ChunkReader {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            if self.fail {
+                return Err(io::Error::other("failed"));
+            }
+            if self.buffer.is_empty() {
+                self.refills += 1;
+                self.buffer = self.chunks.pop().unwrap_or_default();
+            }
+            Ok(self.buffer)
+        }
+
+        fn consume(&mut self, amount: usize) {
+            self.buffer = &self.buffer[amount..];
+        }
+    }
+
+    #[test]
+    fn test_hashing_reader_consume() {
+        let mut reader = HashingReader::new(ChunkReader::new(&[&TEXT[..10], &TEXT[10..]]));
+        assert_eq!(reader.fill_buf().unwrap(), &TEXT[..10]);
+        reader.consume(4);
+        reader.consume(0);
+        reader.consume(6);
+        let mut rest = Vec::new();
+        loop {
+            let buffered = reader.fill_buf().unwrap();
+            if buffered.is_empty() {
+                break;
+            }
+            let length = buffered.len();
+            rest.extend_from_slice(buffered);
+            reader.consume(length);
+        }
+        assert_eq!(rest, &TEXT[10..]);
+
+        // Consuming nothing at end of file must not ask the inner reader for
+        // more input, which on a terminal would wait for a second EOF.
+        let refills = reader.get_ref().refills;
+        reader.consume(0);
+        assert_eq!(reader.get_ref().refills, refills);
+        assert_eq!(reader.finalize().1, sha256_bytes(TEXT));
+    }
+
+    #[test]
+    fn test_hashing_reader_consume_error() {
+        let mut reader = HashingReader::new(ChunkReader::new(&[TEXT]));
+        reader.fill_buf().unwrap();
+        reader.inner.fail = true;
+        reader.consume(3);
+        reader.inner.fail = false;
+
+        // The bytes couldn't be hashed, so the next read reports why instead
+        // of leaving them out of the digest.
+        assert_eq!(reader.fill_buf().unwrap_err().to_string(), "failed");
+        assert_eq!(reader.fill_buf().unwrap(), &TEXT[3..]);
+    }
+
+    #[test]
+    fn test_hashing_writer_short_writes() {
+        let mut writer = HashingWriter::new(ShortWriter(Vec::new()));
+        assert_eq!(writer.write(TEXT).unwrap(), 3);
+        writer.write_all(&TEXT[3..]).unwrap();
+
+        let (inner, digest) = writer.finalize();
+        assert_eq!(inner.0, TEXT);
+        assert_eq!(digest, sha256_bytes(TEXT));
+    }
+
+    #[test]
+    fn test_build_hasher() {
+        let keyed = Sha256BuildHasher::with_key(&[7; 32]);
+        assert_eq!(keyed.hash_one("key"), keyed.hash_one("key"));
+        assert_ne!(keyed.hash_one("key"), keyed.hash_one("other key"));
+        assert_ne!(
+            keyed.hash_one("key"),
+            Sha256BuildHasher::with_key(&[8; 32]).hash_one("key")
+        );
+
+        let mut block = [0u8; 64];
+        block[..32].copy_from_slice(&[7; 32]);
+        let mut hasher = keyed.build_hasher();
+        hasher.write(b"abc");
+        let expected = sha256_bytes(&[&block[..], b"abc"].concat());
+        assert_eq!(hasher.finish().to_be_bytes(), expected.as_bytes()[..8]);
+
+        let mut map = HashMap::with_hasher(Sha256BuildHasher::new());
+        map.insert("one", 1);
+        map.insert("two", 2);
+        assert_eq!(map.get("one"), Some(&1));
+        assert_eq!(map.get("two"), Some(&2));
+        assert_eq!(map.get("three"), None);
+    }
+}
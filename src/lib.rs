@@ -3,14 +3,104 @@
 // This software is released under the MIT License.
 // https://opensource.org/licenses/MIT
 
-use std::fmt::Write;
-use std::io::Read;
-use std::mem::transmute;
+#![cfg_attr(not(feature = "std"), no_std)]
+// Unsafe code is confined to the SIMD backend modules and `hash_file_mmap`,
+// which are only built with the opt-in `simd` and `mmap` features.
+// Every other module forbids it itself.
+#![cfg_attr(not(any(feature = "simd", feature = "mmap")), forbid(unsafe_code))]
+#![cfg_attr(any(feature = "simd", feature = "mmap"), deny(unsafe_code))]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[macro_use]
+mod rounds;
+
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+mod aarch64;
+#[cfg(feature = "async")]
+mod async_io;
+#[cfg(all(test, feature = "std"))]
+mod cavp;
+mod const_hash;
+mod digest;
+#[cfg(feature = "std")]
+mod file;
+mod hkdf;
+mod hmac;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "alloc")]
+mod merkle;
+#[cfg(feature = "alloc")]
+mod merkle_log;
+#[cfg(all(feature = "mmap", unix))]
+mod mmap;
+#[cfg(feature = "alloc")]
+mod multi_buffer;
+#[cfg(all(feature = "alloc", feature = "simd", target_arch = "x86_64"))]
+mod multi_buffer_x86;
+mod pbkdf2;
+mod sha512;
+#[cfg(feature = "alloc")]
+mod sha_crypt;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sha_ni;
+mod state;
+#[cfg(test)]
+mod test_util;
+
+#[cfg(feature = "tokio")]
+pub use async_io::hash_tokio_reader;
+#[cfg(feature = "async")]
+pub use async_io::{hash_async_reader, HashingAsyncReader};
+pub use const_hash::sha256_const;
+pub use digest::{Digest, ParseDigestError};
+#[cfg(feature = "std")]
+pub use file::{
+    hash_file, hash_file_with_progress, hash_files, hash_reader, hash_reader_with_progress,
+};
+pub use hkdf::{hkdf, hkdf_expand, hkdf_extract, HkdfError, HKDF_MAX_OUTPUT_LENGTH};
+pub use hmac::{hmac_sha256, verify_hmac_sha256, Hmac};
+#[cfg(feature = "std")]
+pub use io::{HashingReader, HashingWriter, Sha256BuildHasher, Sha256Hasher};
+#[cfg(feature = "alloc")]
+pub use merkle::{
+    leaf_hash, node_hash, ConsistencyProof, InclusionProof, MerkleTree, ParseProofError,
+};
+#[cfg(feature = "alloc")]
+pub use merkle_log::MerkleLog;
+#[cfg(all(feature = "mmap", unix))]
+pub use mmap::hash_file_mmap;
+#[cfg(feature = "alloc")]
+pub use multi_buffer::hash_many;
+pub use pbkdf2::{pbkdf2_sha256, Pbkdf2Error};
+#[cfg(feature = "alloc")]
+pub use pbkdf2::{verify_pbkdf2_sha256, Pbkdf2Hash};
+#[cfg(feature = "alloc")]
+pub use sha512::{sha384, sha512, sha512_224, sha512_256};
+pub use sha512::{
+    sha384_bytes, sha512_224_bytes, sha512_256_bytes, sha512_bytes, Sha384, Sha512, Sha512_224,
+    Sha512_256,
+};
+#[cfg(feature = "alloc")]
+pub use sha_crypt::{sha256_crypt, verify_sha256_crypt, ShaCryptError};
+pub use state::{ParseStateError, Sha256State};
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt;
 
 const SQRT_CONST: [u32; 8] = [
     0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
 ];
 
+// Second 32 bits of the fractional parts of the square roots of the 9th
+// through 16th primes, used as the SHA-224 initial hash value.
+const SHA224_CONST: [u32; 8] = [
+    0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4,
+];
+
 const CBRT_CONST: [u32; 64] = [
     0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
     0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
@@ -22,141 +112,383 @@ const CBRT_CONST: [u32; 64] = [
     0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
 ];
 
+/// Convenience wrapper returning the digest as lowercase hex.
+#[cfg(feature = "alloc")]
 pub fn sha256(input: &str) -> String {
-    let input_bytes = input.as_bytes();
-    let padded = apply_padding(input_bytes);
-    let blocks = create_blocks(padded);
+    sha256_bytes(input.as_bytes()).to_hex()
+}
 
-    let mut hash = SQRT_CONST;
-    for block in blocks {
-        let schedule = create_message_schedule(&block);
-        hash = do_compression(hash, &schedule);
-    }
+pub fn sha256_bytes(input: &[u8]) -> Digest {
+    let mut hasher = Sha256::new();
+    hasher.update(input);
+    hasher.finalize()
+}
 
-    get_digest(&hash)
+/// Convenience wrapper returning the digest as lowercase hex.
+#[cfg(feature = "alloc")]
+pub fn sha224(input: &str) -> String {
+    sha224_bytes(input.as_bytes()).to_hex()
 }
 
-fn apply_padding(bytes: &[u8]) -> Vec<u8> {
-    let byte_length = bytes.len();
-    let bit_length = byte_length * 8;
+/// Hashes the first `bit_length` bits of `message`, for messages that don't
+/// end on a byte boundary. Bits are taken from the most significant end of
+/// each byte, as in FIPS 180-4.
+pub fn sha256_bits(message: &[u8], bit_length: u64) -> Result<Digest, BitLengthError> {
+    let available = u64::try_from(message.len())
+        .ok()
+        .and_then(|length| length.checked_mul(8));
+    if available.is_none_or(|available| bit_length > available) {
+        return Err(BitLengthError::MessageTooShort);
+    }
 
-    let mut chunk_buffer = Vec::new();
-    chunk_buffer.extend_from_slice(bytes);
-    chunk_buffer.push(0x80);
+    // `bit_length` fits in the message, so the whole bytes fit in a usize.
+    let whole_bytes = (bit_length / 8) as usize;
+    let bits = (bit_length % 8) as u8;
+    let mut hasher = Sha256::new();
+    hasher.update(&message[..whole_bytes]);
+    if bits == 0 {
+        return Ok(hasher.finalize());
+    }
+    hasher.finalize_bits(message[whole_bytes], bits)
+}
+
+pub fn sha224_bytes(input: &[u8]) -> Digest<28> {
+    let mut hasher = Sha224::new();
+    hasher.update(input);
+    hasher.finalize()
+}
 
-    let padding_length = (55 - byte_length as isize).rem_euclid(64).abs() as usize;
-    chunk_buffer.append(&mut vec![0x00; padding_length]);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateError {
+    /// The message would be longer than 2^64 - 1 bits, the most SHA-256 can
+    /// encode in its padding.
+    MessageTooLong,
+}
 
-    for i in (0..8).rev() {
-        chunk_buffer.push((bit_length >> i * 8) as u8);
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MessageTooLong => f.write_str("message is longer than 2^64 - 1 bits"),
+        }
     }
+}
+
+impl core::error::Error for UpdateError {}
 
-    chunk_buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitLengthError {
+    /// The bit length was more than the message holds.
+    MessageTooShort,
+    /// A partial final byte was given with 8 or more bits.
+    InvalidPartialBits(u8),
 }
 
-fn create_blocks(padded: Vec<u8>) -> Vec<[u8; 64]> {
-    let mut blocks = Vec::new();
-    for mut chunk in padded.chunks(64) {
-        let mut block = [0u8; 64];
-        chunk.read(&mut block).unwrap();
-        blocks.push(block);
+impl fmt::Display for BitLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MessageTooShort => f.write_str("bit length is longer than the message"),
+            Self::InvalidPartialBits(bits) => {
+                write!(f, "a partial byte can't hold {} bits", bits)
+            }
+        }
     }
+}
+
+impl core::error::Error for BitLengthError {}
 
-    blocks
+/// Streaming SHA-256 state. Full 64-byte blocks are compressed as soon as they
+/// arrive, so only a partial block is ever buffered.
+#[derive(Clone)]
+pub struct Sha256 {
+    hash: [u32; 8],
+    buffer: [u8; 64],
+    buffered: usize,
+    bit_length: u64,
 }
 
-fn create_message_schedule(block: &[u8; 64]) -> [u32; 64] {
-    let mut schedule: [u32; 64] = [0; 64];
+impl Sha256 {
+    pub fn new() -> Self {
+        Self::with_initial(SQRT_CONST)
+    }
 
-    for i in 0..16 {
-        schedule[i] = u32::from_be_bytes(block[i * 4..(i + 1) * 4].try_into().unwrap());
+    fn with_initial(initial: [u32; 8]) -> Self {
+        Self {
+            hash: initial,
+            buffer: [0; 64],
+            buffered: 0,
+            bit_length: 0,
+        }
     }
 
-    for i in 16..64 {
-        let calculated: u32 = sig1(schedule[i - 2])
-            .wrapping_add(schedule[i - 7])
-            .wrapping_add(sig0(schedule[i - 15]))
-            .wrapping_add(schedule[i - 16]);
-        schedule[i] = calculated;
+    /// Adds `bytes` to the message.
+    ///
+    /// # Panics
+    ///
+    /// If the message grows past 2^64 - 1 bits. Use [`Sha256::try_update`] to
+    /// handle that as an error.
+    pub fn update(&mut self, bytes: &[u8]) {
+        if let Err(error) = self.try_update(bytes) {
+            panic!("{}", error);
+        }
     }
 
-    schedule
-}
+    /// Adds `bytes` to the message, or leaves the state untouched if the
+    /// message would grow past the SHA-256 limit of 2^64 - 1 bits.
+    pub fn try_update(&mut self, mut bytes: &[u8]) -> Result<(), UpdateError> {
+        self.bit_length = u64::try_from(bytes.len())
+            .ok()
+            .and_then(|length| length.checked_mul(8))
+            .and_then(|bits| self.bit_length.checked_add(bits))
+            .ok_or(UpdateError::MessageTooLong)?;
+
+        if self.buffered > 0 {
+            let take = (64 - self.buffered).min(bytes.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&bytes[..take]);
+            self.buffered += take;
+            bytes = &bytes[take..];
+
+            if self.buffered < 64 {
+                return Ok(());
+            }
+            compress(&mut self.hash, &self.buffer);
+            self.buffered = 0;
+        }
+
+        let full_blocks = bytes.len() - bytes.len() % 64;
+        compress(&mut self.hash, &bytes[..full_blocks]);
+
+        let remainder = &bytes[full_blocks..];
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffered = remainder.len();
+        Ok(())
+    }
 
-fn do_compression(initial: [u32; 8], schedule: &[u32; 64]) -> [u32; 8] {
-    let mut registers: [u32; 8] = initial;
+    pub fn finalize(self) -> Digest {
+        get_digest(&self.finish())
+    }
 
-    for i in 0..64 {
-        let word = schedule[i];
-        let constant = CBRT_CONST[i];
+    /// Exports the state so hashing can be resumed later with
+    /// [`Sha256::from_state`].
+    pub fn state(&self) -> Sha256State {
+        let mut buffer = [0; 64];
+        buffer[..self.buffered].copy_from_slice(&self.buffer[..self.buffered]);
+
+        Sha256State {
+            hash: self.hash,
+            bit_length: self.bit_length,
+            buffer,
+            buffered: self.buffered,
+        }
+    }
 
-        let temp1 = usig1(registers[4])
-            .wrapping_add(ch(registers[4], registers[5], registers[6]))
-            .wrapping_add(registers[7])
-            .wrapping_add(constant)
-            .wrapping_add(word);
-        let temp2 = usig0(registers[0]).wrapping_add(maj(registers[0], registers[1], registers[2]));
+    pub fn from_state(state: &Sha256State) -> Self {
+        Self {
+            hash: state.hash,
+            buffer: state.buffer,
+            buffered: state.buffered,
+            bit_length: state.bit_length,
+        }
+    }
 
-        registers.rotate_right(1);
-        registers[0] = temp1.wrapping_add(temp2);
-        registers[4] = registers[4].wrapping_add(temp1);
+    /// Finishes a message whose length isn't a whole number of bytes, taking
+    /// its last `bits` bits from the high end of `last`. The remaining bits of
+    /// `last` are ignored.
+    pub fn finalize_bits(self, last: u8, bits: u8) -> Result<Digest, BitLengthError> {
+        if bits >= 8 {
+            return Err(BitLengthError::InvalidPartialBits(bits));
+        }
+        Ok(get_digest(&self.finish_bits(last, bits)))
     }
 
-    for i in 0..8 {
-        registers[i] = initial[i].wrapping_add(registers[i]);
+    /// Pads the message and returns the final hash words.
+    fn finish(self) -> [u32; 8] {
+        self.finish_bits(0, 0)
     }
 
-    registers
+    /// Appends the top `bits` bits of `last`, which must be fewer than 8, then
+    /// pads the message and returns the final hash words.
+    fn finish_bits(mut self, last: u8, bits: u8) -> [u32; 8] {
+        // Byte updates keep the length a multiple of 8 bits, so adding up to 7
+        // more can't overflow.
+        self.bit_length += u64::from(bits);
+
+        // A single 1 bit, then zeros and the big-endian bit length in the last
+        // 8 bytes, spilling into a second block when fewer than 65 bits are
+        // free. `buffered` is always below 64 between updates.
+        let kept = !(0xffu8 >> bits);
+        self.buffer[self.buffered..].fill(0);
+        self.buffer[self.buffered] = last & kept | 0x80 >> bits;
+        if self.buffered >= 56 {
+            compress(&mut self.hash, &self.buffer);
+            self.buffer = [0; 64];
+        }
+        self.buffer[56..].copy_from_slice(&self.bit_length.to_be_bytes());
+        compress(&mut self.hash, &self.buffer);
+
+        self.hash
+    }
 }
 
-fn get_digest(compressed: &[u32; 8]) -> String {
-    let mut bytes: [u8; 32] = [0; 32];
-    for i in 0..8 {
-        let transmuted: [u8; 4] = unsafe { transmute(compressed[i].to_be()) };
-        bytes[i * 4..(i + 1) * 4].copy_from_slice(&transmuted);
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    let mut digest = String::with_capacity(64);
-    for byte in bytes {
-        write!(digest, "{:02x}", byte).unwrap();
+/// Streaming SHA-224 state. SHA-224 is SHA-256 with a different initial hash
+/// value and the output truncated to 28 bytes.
+#[derive(Clone)]
+pub struct Sha224 {
+    inner: Sha256,
+}
+
+impl Sha224 {
+    pub fn new() -> Self {
+        Self {
+            inner: Sha256::with_initial(SHA224_CONST),
+        }
+    }
+
+    /// Adds `bytes` to the message.
+    ///
+    /// # Panics
+    ///
+    /// If the message grows past 2^64 - 1 bits, see [`Sha256::update`].
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.inner.update(bytes);
+    }
+
+    /// Adds `bytes` to the message, see [`Sha256::try_update`].
+    pub fn try_update(&mut self, bytes: &[u8]) -> Result<(), UpdateError> {
+        self.inner.try_update(bytes)
+    }
+
+    pub fn finalize(self) -> Digest<28> {
+        get_digest(&self.inner.finish())
     }
-    digest
 }
 
-#[inline]
-fn sig0(x: u32) -> u32 {
-    x.rotate_right(7) ^ x.rotate_right(18) ^ x >> 3
+impl Default for Sha224 {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[inline]
-fn sig1(x: u32) -> u32 {
-    x.rotate_right(17) ^ x.rotate_right(19) ^ x >> 10
+/// Compresses every 64-byte block in `blocks` into `hash`, using the CPU's
+/// SHA-256 instructions when it has them.
+fn compress(hash: &mut [u32; 8], blocks: &[u8]) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        if sha_ni::try_compress(hash, blocks) {
+            return;
+        }
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    {
+        if aarch64::try_compress(hash, blocks) {
+            return;
+        }
+    }
+
+    compress_portable(hash, blocks);
 }
 
-#[inline]
-fn usig0(x: u32) -> u32 {
-    x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)
+fn compress_portable(hash: &mut [u32; 8], blocks: &[u8]) {
+    let (blocks, _) = blocks.as_chunks::<64>();
+    for block in blocks {
+        let schedule = create_message_schedule(block);
+        *hash = do_compression(*hash, &schedule);
+    }
 }
 
-#[inline]
-fn usig1(x: u32) -> u32 {
-    x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)
+const fn create_message_schedule(block: &[u8; 64]) -> [u32; 64] {
+    let mut schedule: [u32; 64] = [0; 64];
+
+    let mut i = 0;
+    while i < 16 {
+        schedule[i] = u32::from_be_bytes([
+            block[i * 4],
+            block[i * 4 + 1],
+            block[i * 4 + 2],
+            block[i * 4 + 3],
+        ]);
+        i += 1;
+    }
+
+    while i < 64 {
+        let calculated: u32 = sig1(schedule[i - 2])
+            .wrapping_add(schedule[i - 7])
+            .wrapping_add(sig0(schedule[i - 15]))
+            .wrapping_add(schedule[i - 16]);
+        schedule[i] = calculated;
+        i += 1;
+    }
+
+    schedule
 }
 
-#[inline]
-fn ch(x: u32, y: u32, z: u32) -> u32 {
-    (x & y) ^ (!x & z)
+const fn do_compression(initial: [u32; 8], schedule: &[u32; 64]) -> [u32; 8] {
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = initial;
+
+    let mut i = 0;
+    while i < 64 {
+        let temp1 = usig1(e)
+            .wrapping_add(ch(e, f, g))
+            .wrapping_add(h)
+            .wrapping_add(CBRT_CONST[i])
+            .wrapping_add(schedule[i]);
+        let temp2 = usig0(a).wrapping_add(maj(a, b, c));
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+        i += 1;
+    }
+
+    [
+        initial[0].wrapping_add(a),
+        initial[1].wrapping_add(b),
+        initial[2].wrapping_add(c),
+        initial[3].wrapping_add(d),
+        initial[4].wrapping_add(e),
+        initial[5].wrapping_add(f),
+        initial[6].wrapping_add(g),
+        initial[7].wrapping_add(h),
+    ]
 }
 
-#[inline]
-fn maj(x: u32, y: u32, z: u32) -> u32 {
-    (x & y) ^ (x & z) ^ (y & z)
+fn get_digest<const N: usize>(compressed: &[u32; 8]) -> Digest<N> {
+    let mut bytes: [u8; N] = [0; N];
+    for (chunk, word) in bytes.chunks_mut(4).zip(compressed) {
+        chunk.copy_from_slice(&word.to_be_bytes()[..chunk.len()]);
+    }
+
+    Digest(bytes)
 }
 
+round_functions!(
+    u32,
+    sig0: (7, 18, 3),
+    sig1: (17, 19, 10),
+    usig0: (2, 13, 22),
+    usig1: (6, 11, 25)
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "alloc")]
+    use crate::test_util::from_hex;
+    #[cfg(feature = "alloc")]
+    use alloc::{string::ToString, vec::Vec};
 
+    #[cfg(feature = "alloc")]
     #[test]
     fn test_sha256() {
         assert_eq!(
@@ -192,4 +524,249 @@ mod tests {
             "bdbb529d28016a81b32bfc5a0d58bb9787abdb229c2bb18f0d3aa8c635c69e0f"
         );
     }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_sha256_bytes() {
+        let all_bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(
+            sha256_bytes(&all_bytes).to_hex(),
+            "40aff2e9d2d8922e47afd4648e6967497158785fbd1da870e7110266bf944880"
+        );
+        assert_eq!(
+            sha256_bytes(&[0xff, 0xfe, 0x80, 0xc0]).to_hex(),
+            "093e6113b833eae0e5cb570c28e8da500bed6cd5c36af2db16a70bdf8c2b8e78"
+        );
+        assert_eq!(
+            sha256_bytes(&[0; 64]).to_hex(),
+            "f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b"
+        );
+        assert_eq!(
+            sha256_bytes(b"https://lerners.io").to_hex(),
+            sha256("https://lerners.io")
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_streaming() {
+        let input = "The quick brown fox jumps over the lazy dog. ".repeat(10);
+        let expected = sha256_bytes(input.as_bytes());
+
+        for split in [0, 1, 55, 56, 63, 64, 65, 127, 128, input.len()] {
+            let mut hasher = Sha256::new();
+            hasher.update(&input.as_bytes()[..split]);
+            hasher.update(&input.as_bytes()[split..]);
+            assert_eq!(hasher.finalize(), expected);
+        }
+
+        let mut hasher = Sha256::new();
+        for byte in input.bytes() {
+            hasher.update(&[byte]);
+        }
+        assert_eq!(hasher.finalize(), expected);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_streaming_million_a() {
+        let chunk = [b'a'; 1000];
+        let mut hasher = Sha256::new();
+        for _ in 0..1000 {
+            hasher.update(&chunk);
+        }
+        assert_eq!(
+            hasher.finalize().to_hex(),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    /// A hasher that has already absorbed `bit_length` bits, with the buffered
+    /// tail zeroed.
+    fn hasher_at(bit_length: u64) -> Sha256 {
+        Sha256 {
+            hash: SQRT_CONST,
+            buffer: [0; 64],
+            buffered: (bit_length / 8 % 64) as usize,
+            bit_length,
+        }
+    }
+
+    #[test]
+    fn test_length_limit() {
+        let last_byte = u64::MAX - 7;
+
+        let mut hasher = hasher_at(last_byte - 8);
+        assert_eq!(hasher.try_update(&[0]), Ok(()));
+        assert_eq!(hasher.bit_length, last_byte);
+        assert_eq!(hasher.try_update(&[]), Ok(()));
+        assert_eq!(hasher.try_update(&[0]), Err(UpdateError::MessageTooLong));
+        assert_eq!(hasher.bit_length, last_byte);
+
+        let mut hasher = hasher_at(last_byte - 8 * 100);
+        assert_eq!(
+            hasher.try_update(&[0; 101]),
+            Err(UpdateError::MessageTooLong)
+        );
+        assert_eq!(hasher.try_update(&[0; 100]), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "message is longer than 2^64 - 1 bits")]
+    fn test_update_past_limit_panics() {
+        hasher_at(u64::MAX - 7).update(&[0]);
+    }
+
+    #[test]
+    fn test_padding_near_wraparound() {
+        // Lengths either side of 2^32 bits, where a 32-bit length would wrap,
+        // and at the top of the u64 range.
+        for bit_length in [
+            (1 << 32) - 8,
+            1 << 32,
+            (1 << 32) + 8,
+            u64::MAX - 63 * 8 - 7,
+            u64::MAX - 7,
+        ] {
+            let hasher = hasher_at(bit_length);
+            let buffered = hasher.buffered;
+
+            let mut expected = SQRT_CONST;
+            let mut padding = [0u8; 128];
+            padding[buffered] = 0x80;
+            let blocks = if buffered < 56 { 64 } else { 128 };
+            padding[blocks - 8..blocks].copy_from_slice(&bit_length.to_be_bytes());
+            compress_portable(&mut expected, &padding[..blocks]);
+
+            assert_eq!(hasher.finish(), expected, "bit length {}", bit_length);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_bit_messages() {
+        // Digests were cross-checked against a separate bit-level
+        // implementation of FIPS 180-4. The 447, 449 and 511 bit messages sit
+        // either side of the point where padding spills into a second block.
+        let vectors = [
+            (
+                "00",
+                1,
+                "bd4f9e98beb68c6ead3243b1b4c7fed75fa4feaab1f84795cbd8a98676a2a375",
+            ),
+            (
+                "80",
+                1,
+                "b9debf7d52f36e6468a54817c1fa071166c3a63d384850e1575b42f702dc5aa1",
+            ),
+            (
+                "68",
+                5,
+                "d6d3e02a31a84a8caa9718ed6c2057be09db45e7823eb5079ce7a573a3760f95",
+            ),
+            (
+                "98",
+                5,
+                "8f136783ea6f000dccc4295d4db99b648f1c8f483b27248db103ba7cd567dbba",
+            ),
+            (
+                "49b2aec2594bbe3a3b117542d94ac880",
+                123,
+                "a65838fb6c923e3d8ed23610ad712f6388ffe4137b04b401aa1bd278f0ac8651",
+            ),
+            (
+                "1c2e2bb8569d806c1251dcc9bee389120ebaeea3c2d8545a78760c5aa65845b8\
+                 5de4d4bab5b9e452ccec7ffa8effb5e8ecb3e9f971a65589",
+                447,
+                "fb7c9f8efa20be0ed797e8437f148a174b6d1863fd5525ea14f12e39248ab276",
+            ),
+            (
+                "f59e9bd09f6afabb26ae0461361e198b743645887d6b1ed8101db9b8587f0c2a\
+                 3a220c140abf8241505e00c5167e4d1202b03992acfa0f9de5",
+                449,
+                "63d7220ac32894c2b8803eb265bc26e024e7309b71e446b28bcc930bee1ec9d9",
+            ),
+            (
+                "1787cd4ef2732fa1340ce541c9f9a749ae8486d609471d811143525731e87610\
+                 7e77e325802974b883d88e024d12c4d152382c7b34330a5d76356f0cede89ec2",
+                511,
+                "2a9ccb1c280f3921afa557dc1fb90db3903716e715ea66560686ddfd7457ddcb",
+            ),
+        ];
+
+        for (message, bit_length, expected) in vectors {
+            assert_eq!(
+                sha256_bits(&from_hex(message), bit_length).map(|digest| digest.to_hex()),
+                Ok(expected.to_string()),
+                "{} bits",
+                bit_length
+            );
+        }
+    }
+
+    #[test]
+    fn test_bit_messages_match_bytes() {
+        let message = b"abc";
+        assert_eq!(sha256_bits(message, 24), Ok(sha256_bytes(message)));
+        assert_eq!(sha256_bits(message, 0), Ok(sha256_bytes(b"")));
+
+        // Bits past the bit length are ignored.
+        assert_eq!(sha256_bits(&[0x6f], 5), sha256_bits(&[0x68], 5));
+        let mut hasher = Sha256::new();
+        hasher.update(b"ab");
+        assert_eq!(hasher.finalize_bits(0x63, 0), Ok(sha256_bytes(b"ab")));
+    }
+
+    #[test]
+    fn test_bit_message_errors() {
+        assert_eq!(
+            sha256_bits(b"abc", 25),
+            Err(BitLengthError::MessageTooShort)
+        );
+        assert_eq!(
+            Sha256::new().finalize_bits(0, 8),
+            Err(BitLengthError::InvalidPartialBits(8))
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_fips_examples() {
+        let two_block = "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+
+        assert_eq!(
+            sha256("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256(two_block),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(
+            sha224("abc"),
+            "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7"
+        );
+        assert_eq!(
+            sha224(two_block),
+            "75388b16512776cc5dba5da1fd890150b0c6455cb4f58b1952522525"
+        );
+        assert_eq!(
+            sha224(""),
+            "d14a028c2a3a2bc9476102bb288234c415a2b01f828ea62ac5b3e42f"
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_sha224_streaming() {
+        let chunk = [b'a'; 1000];
+        let mut hasher = Sha224::new();
+        for _ in 0..1000 {
+            hasher.update(&chunk);
+        }
+        assert_eq!(
+            hasher.finalize().to_hex(),
+            "20794655980c91d8bbb4c1ea97618a4bf03f42581948b2ee4ee7ad67"
+        );
+    }
 }
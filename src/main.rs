@@ -0,0 +1,532 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! A `sha256sum`-compatible command-line tool built on the crate's streaming
+//! hasher.
+
+#![forbid(unsafe_code)]
+
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use sha256_rust::{hash_file, hash_reader, Digest};
+
+const NAME: &str = "sha256";
+
+const USAGE: &str = "\
+Usage: sha256 [OPTION]... [FILE]...
+Print or check SHA256 (256-bit) checksums.
+
+With no FILE, or when FILE is -, read standard input.
+
+  -b, --binary         read in binary mode
+  -c, --check          read checksums from the FILEs and check them
+      --tag            create a BSD-style checksum
+  -t, --text           read in text mode (default)
+
+The following five options are useful only when verifying checksums:
+      --ignore-missing  don't fail or report status for missing files
+      --quiet          don't print OK for each successfully verified file
+      --status         don't output anything, status code shows success
+      --strict         exit non-zero for improperly formatted checksum lines
+  -w, --warn           warn about improperly formatted checksum lines
+
+      --help           display this help and exit
+      --version        output version information and exit
+";
+
+#[derive(Default)]
+struct Options {
+    binary: bool,
+    text: bool,
+    tag: bool,
+    check: bool,
+    ignore_missing: bool,
+    quiet: bool,
+    status: bool,
+    strict: bool,
+    warn: bool,
+    files: Vec<OsString>,
+}
+
+enum Command {
+    Run(Options),
+    Help,
+    Version,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<OsString> = env::args_os().skip(1).collect();
+
+    let options = match parse_args(&args) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("{} {}", NAME, env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("{}: {}", NAME, message);
+            eprintln!("Try '{} --help' for more information.", NAME);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let succeeded = if options.check {
+        check_files(&options)
+    } else {
+        hash_files(&options)
+    };
+
+    if succeeded {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Parses the arguments, keeping file names as given so names that aren't
+/// valid UTF-8 still reach the file system intact.
+fn parse_args(args: &[OsString]) -> Result<Command, String> {
+    let mut options = Options::default();
+    let mut only_files = false;
+
+    for arg in args {
+        if only_files || arg == "-" || !arg.as_encoded_bytes().starts_with(b"-") {
+            options.files.push(arg.clone());
+            continue;
+        }
+
+        let Some(arg) = arg.to_str() else {
+            return Err(format!("unrecognized option '{}'", arg.to_string_lossy()));
+        };
+        match arg {
+            "--" => only_files = true,
+            "--binary" => options.binary = true,
+            "--check" => options.check = true,
+            "--tag" => options.tag = true,
+            "--text" => options.text = true,
+            "--ignore-missing" => options.ignore_missing = true,
+            "--quiet" => options.quiet = true,
+            "--status" => options.status = true,
+            "--strict" => options.strict = true,
+            "--warn" => options.warn = true,
+            "--help" => return Ok(Command::Help),
+            "--version" => return Ok(Command::Version),
+            long if long.starts_with("--") => {
+                return Err(format!("unrecognized option '{}'", long));
+            }
+            short => {
+                for flag in short.chars().skip(1) {
+                    match flag {
+                        'b' => options.binary = true,
+                        'c' => options.check = true,
+                        't' => options.text = true,
+                        'w' => options.warn = true,
+                        _ => return Err(format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if options.files.is_empty() {
+        options.files.push(OsString::from("-"));
+    }
+
+    if options.check {
+        if options.tag {
+            return Err(String::from(
+                "the --tag option is meaningless when verifying checksums",
+            ));
+        }
+        if options.binary || options.text {
+            return Err(String::from(
+                "the --binary and --text options are meaningless when verifying checksums",
+            ));
+        }
+    } else {
+        let check_only = [
+            (options.ignore_missing, "--ignore-missing"),
+            (options.quiet, "--quiet"),
+            (options.status, "--status"),
+            (options.strict, "--strict"),
+            (options.warn, "--warn"),
+        ];
+        if let Some((_, flag)) = check_only.iter().find(|(set, _)| *set) {
+            return Err(format!(
+                "the {} option is meaningful only when verifying checksums",
+                flag
+            ));
+        }
+        if options.tag && options.text {
+            return Err(String::from("--tag does not support --text mode"));
+        }
+    }
+
+    Ok(Command::Run(options))
+}
+
+fn open_input(name: &Path) -> io::Result<Box<dyn Read>> {
+    if name == Path::new("-") {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(File::open(name)?))
+    }
+}
+
+/// Hashes a named file, or standard input for `-`.
+fn hash_input(name: &Path) -> io::Result<Digest> {
+    if name == Path::new("-") {
+        hash_reader(io::stdin().lock())
+    } else {
+        hash_file(name)
+    }
+}
+
+/// The path for a name read from a check list. Names are raw bytes on Unix,
+/// as in GNU coreutils; elsewhere they have to be UTF-8.
+#[cfg(unix)]
+fn path_from_bytes(name: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+
+    PathBuf::from(OsStr::from_bytes(name))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(name: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(name).into_owned())
+}
+
+fn hash_files(options: &Options) -> bool {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut succeeded = true;
+
+    for name in &options.files {
+        let path = Path::new(name);
+        let digest = match hash_input(path) {
+            Ok(digest) => digest,
+            Err(error) => {
+                eprintln!("{}: {}: {}", NAME, path.display(), describe_error(&error));
+                succeeded = false;
+                continue;
+            }
+        };
+
+        let (escaped, name) = escape_name(name.as_encoded_bytes());
+        let mut line = Vec::new();
+        if escaped {
+            line.push(b'\\');
+        }
+        if options.tag {
+            line.extend_from_slice(b"SHA256 (");
+            line.extend_from_slice(&name);
+            line.extend_from_slice(format!(") = {}", digest).as_bytes());
+        } else {
+            let marker = if options.binary { '*' } else { ' ' };
+            line.extend_from_slice(format!("{} {}", digest, marker).as_bytes());
+            line.extend_from_slice(&name);
+        }
+        line.push(b'\n');
+
+        if out.write_all(&line).is_err() {
+            return false;
+        }
+    }
+
+    succeeded
+}
+
+#[derive(Default)]
+struct CheckSummary {
+    formatted: usize,
+    improperly_formatted: usize,
+    unreadable: usize,
+    mismatched: usize,
+    verified: usize,
+}
+
+fn check_files(options: &Options) -> bool {
+    let mut succeeded = true;
+
+    for list in &options.files {
+        let list = Path::new(list);
+        let reader = match open_input(list) {
+            Ok(reader) => BufReader::new(reader),
+            Err(error) => {
+                eprintln!("{}: {}: {}", NAME, list.display(), describe_error(&error));
+                succeeded = false;
+                continue;
+            }
+        };
+
+        succeeded &= check_list(options, list, reader);
+    }
+
+    succeeded
+}
+
+fn check_list(options: &Options, list: &Path, mut reader: impl BufRead) -> bool {
+    let list = list.display();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut summary = CheckSummary::default();
+    let mut line = Vec::new();
+    let mut line_number = 0;
+
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => break,
+            Ok(_) => line_number += 1,
+            Err(error) => {
+                eprintln!("{}: {}: {}", NAME, list, describe_error(&error));
+                return false;
+            }
+        }
+
+        let text = line.strip_suffix(b"\n").unwrap_or(&line);
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        let Some((expected, name)) = parse_check_line(text) else {
+            summary.improperly_formatted += 1;
+            if options.warn {
+                eprintln!(
+                    "{}: {}: {}: improperly formatted SHA256 checksum line",
+                    NAME, list, line_number
+                );
+            }
+            continue;
+        };
+        summary.formatted += 1;
+
+        let path = path_from_bytes(&name);
+        let status = match hash_input(&path) {
+            Ok(actual) if actual == expected => {
+                summary.verified += 1;
+                "OK"
+            }
+            Ok(_) => {
+                summary.mismatched += 1;
+                "FAILED"
+            }
+            Err(error) if options.ignore_missing && error.kind() == io::ErrorKind::NotFound => {
+                continue;
+            }
+            Err(error) => {
+                summary.unreadable += 1;
+                eprintln!("{}: {}: {}", NAME, path.display(), describe_error(&error));
+                "FAILED open or read"
+            }
+        };
+
+        if options.status || (options.quiet && status == "OK") {
+            continue;
+        }
+        let (escaped, printed) = escape_name(&name);
+        let mut output = Vec::new();
+        if escaped {
+            output.push(b'\\');
+        }
+        output.extend_from_slice(&printed);
+        output.extend_from_slice(format!(": {}\n", status).as_bytes());
+        if out.write_all(&output).is_err() {
+            return false;
+        }
+    }
+
+    if summary.formatted == 0 {
+        eprintln!(
+            "{}: {}: no properly formatted SHA256 checksum lines found",
+            NAME, list
+        );
+        return false;
+    }
+
+    if !options.status {
+        if summary.improperly_formatted > 0 {
+            eprintln!(
+                "{}: WARNING: {} {} improperly formatted",
+                NAME,
+                summary.improperly_formatted,
+                plural(summary.improperly_formatted, "line is", "lines are")
+            );
+        }
+        if summary.unreadable > 0 {
+            eprintln!(
+                "{}: WARNING: {} listed {} not be read",
+                NAME,
+                summary.unreadable,
+                plural(summary.unreadable, "file could", "files could")
+            );
+        }
+        if summary.mismatched > 0 {
+            eprintln!(
+                "{}: WARNING: {} computed {} did NOT match",
+                NAME,
+                summary.mismatched,
+                plural(summary.mismatched, "checksum", "checksums")
+            );
+        }
+    }
+
+    if options.ignore_missing && summary.verified == 0 && summary.mismatched == 0 {
+        if !options.status {
+            eprintln!("{}: {}: no file was verified", NAME, list);
+        }
+        return false;
+    }
+
+    summary.unreadable == 0
+        && summary.mismatched == 0
+        && !(options.strict && summary.improperly_formatted > 0)
+}
+
+/// Parses either a `<hex>  <name>` / `<hex> *<name>` line or a BSD-style
+/// `SHA256 (<name>) = <hex>` line. Names are kept as raw bytes.
+fn parse_check_line(line: &[u8]) -> Option<(Digest, Vec<u8>)> {
+    let line = line.trim_ascii_start();
+    let (escaped, line) = match line.strip_prefix(b"\\") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    let (hex, name) = if let Some(rest) = line.strip_prefix(b"SHA256 (") {
+        let split = rest.windows(4).rposition(|window| window == b") = ")?;
+        (&rest[split + 4..], &rest[..split])
+    } else {
+        let hex = line.get(..64)?;
+        let rest = &line[64..];
+        let name = rest
+            .strip_prefix(b"  ")
+            .or_else(|| rest.strip_prefix(b" *"))?;
+        (hex, name)
+    };
+
+    if name.is_empty() {
+        return None;
+    }
+    let digest = std::str::from_utf8(hex).ok()?.parse().ok()?;
+    let name = if escaped {
+        unescape_name(name)?
+    } else {
+        name.to_vec()
+    };
+
+    Some((digest, name))
+}
+
+/// Escapes backslashes and line breaks the way GNU coreutils does, returning
+/// whether the line needs a leading backslash.
+fn escape_name(name: &[u8]) -> (bool, Vec<u8>) {
+    if !name
+        .iter()
+        .any(|byte| matches!(byte, b'\\' | b'\n' | b'\r'))
+    {
+        return (false, name.to_vec());
+    }
+
+    let mut escaped = Vec::with_capacity(name.len() + 2);
+    for &byte in name {
+        match byte {
+            b'\\' => escaped.extend_from_slice(b"\\\\"),
+            b'\n' => escaped.extend_from_slice(b"\\n"),
+            b'\r' => escaped.extend_from_slice(b"\\r"),
+            _ => escaped.push(byte),
+        }
+    }
+    (true, escaped)
+}
+
+fn unescape_name(name: &[u8]) -> Option<Vec<u8>> {
+    let mut unescaped = Vec::with_capacity(name.len());
+    let mut bytes = name.iter();
+
+    while let Some(&byte) = bytes.next() {
+        if byte != b'\\' {
+            unescaped.push(byte);
+            continue;
+        }
+        match bytes.next()? {
+            b'\\' => unescaped.push(b'\\'),
+            b'n' => unescaped.push(b'\n'),
+            b'r' => unescaped.push(b'\r'),
+            _ => return None,
+        }
+    }
+
+    Some(unescaped)
+}
+
+fn describe_error(error: &io::Error) -> String {
+    match error.kind() {
+        io::ErrorKind::NotFound => String::from("No such file or directory"),
+        io::ErrorKind::PermissionDenied => String::from("Permission denied"),
+        _ => error.to_string(),
+    }
+}
+
+fn plural<'a>(count: usize, singular: &'a str, plural: &'a str) -> &'a str {
+    if count == 1 {
+        singular
+    } else {
+        plural
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn test_parse_check_line() {
+        let expected: Digest = ABC.parse().unwrap();
+        let parse = |line: String| parse_check_line(line.as_bytes());
+
+        assert_eq!(
+            parse(format!("{}  abc.txt", ABC)),
+            Some((expected, b"abc.txt".to_vec()))
+        );
+        assert_eq!(
+            parse(format!("{} *abc.txt", ABC)),
+            Some((expected, b"abc.txt".to_vec()))
+        );
+        assert_eq!(
+            parse(format!("SHA256 (a (b).txt) = {}", ABC)),
+            Some((expected, b"a (b).txt".to_vec()))
+        );
+        assert_eq!(
+            parse(format!("\\{}  a\\\\b\\nc", ABC)),
+            Some((expected, b"a\\b\nc".to_vec()))
+        );
+
+        let mut raw = format!("{}  n", ABC).into_bytes();
+        raw.extend_from_slice(b"\xff.bin");
+        assert_eq!(
+            parse_check_line(&raw),
+            Some((expected, b"n\xff.bin".to_vec()))
+        );
+
+        assert_eq!(parse(format!("{} abc.txt", ABC)), None);
+        assert_eq!(parse(format!("{}  ", ABC)), None);
+        assert_eq!(parse(String::from("abc  abc.txt")), None);
+    }
+
+    #[test]
+    fn test_escape_name() {
+        assert_eq!(escape_name(b"plain.txt"), (false, b"plain.txt".to_vec()));
+        assert_eq!(escape_name(b"a\\b\nc"), (true, b"a\\\\b\\nc".to_vec()));
+        assert_eq!(unescape_name(b"a\\\\b\\nc"), Some(b"a\\b\nc".to_vec()));
+    }
+}
@@ -0,0 +1,673 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Merkle trees over SHA-256 as defined for Certificate Transparency in RFC
+//! 6962 and RFC 9162. Leaves and interior nodes are hashed with different
+//! prefixes, `0x00` and `0x01`, so a leaf can never be passed off as a node.
+//!
+//! Inclusion proofs show that a leaf is in a tree, and consistency proofs that
+//! one tree is a prefix of another, as an append-only log must be.
+
+#![forbid(unsafe_code)]
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{sha256_bytes, Digest, Sha256};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+const HASH_LENGTH: usize = 32;
+
+/// The hash of a leaf holding `data`, `SHA-256(0x00 || data)`.
+pub fn leaf_hash(data: &[u8]) -> Digest {
+    let mut hasher = Sha256::new();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// The hash of an interior node, `SHA-256(0x01 || left || right)`.
+pub fn node_hash(left: &Digest, right: &Digest) -> Digest {
+    let mut hasher = Sha256::new();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
+
+/// A Merkle tree built from its leaf hashes. Trees whose size isn't a power of
+/// two are unbalanced as in RFC 6962: the left subtree always holds the
+/// largest power of two leaves smaller than the whole.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MerkleTree {
+    leaves: Vec<Digest>,
+}
+
+impl MerkleTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a tree with a leaf for each item of `entries`.
+    pub fn from_entries<I>(entries: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        Self {
+            leaves: entries
+                .into_iter()
+                .map(|entry| leaf_hash(entry.as_ref()))
+                .collect(),
+        }
+    }
+
+    /// Appends a leaf holding `data`.
+    pub fn push(&mut self, data: &[u8]) {
+        self.leaves.push(leaf_hash(data));
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn leaf_hashes(&self) -> &[Digest] {
+        &self.leaves
+    }
+
+    /// The tree head. An empty tree has the hash of the empty string.
+    pub fn root(&self) -> Digest {
+        subtree_root(&self.leaves)
+    }
+
+    /// The audit path proving that leaf `index` is in the tree, or `None` if
+    /// there is no such leaf.
+    pub fn inclusion_proof(&self, index: usize) -> Option<InclusionProof> {
+        if index >= self.leaves.len() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        inclusion_path(index, 0..self.leaves.len(), &self.range_root(), &mut path);
+        Some(InclusionProof {
+            leaf_index: index as u64,
+            tree_size: self.leaves.len() as u64,
+            path,
+        })
+    }
+
+    /// Proof that the tree of the first `first_size` leaves is a prefix of
+    /// this one, or `None` if `first_size` is larger than the tree.
+    pub fn consistency_proof(&self, first_size: usize) -> Option<ConsistencyProof> {
+        if first_size > self.leaves.len() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        if first_size > 0 {
+            let leaves = 0..self.leaves.len();
+            consistency_path(first_size, leaves, true, &self.range_root(), &mut path);
+        }
+        Some(ConsistencyProof {
+            first_size: first_size as u64,
+            second_size: self.leaves.len() as u64,
+            path,
+        })
+    }
+
+    fn range_root(&self) -> impl Fn(Range<usize>) -> Digest + '_ {
+        |range| subtree_root(&self.leaves[range])
+    }
+}
+
+/// `MTH(D[n])` from RFC 6962 section 2.1.
+fn subtree_root(leaves: &[Digest]) -> Digest {
+    match leaves {
+        [] => sha256_bytes(&[]),
+        [leaf] => *leaf,
+        _ => {
+            let (left, right) = leaves.split_at(split_point(leaves.len()));
+            node_hash(&subtree_root(left), &subtree_root(right))
+        }
+    }
+}
+
+/// The largest power of two smaller than `size`, which must be at least 2.
+pub(crate) fn split_point(size: usize) -> usize {
+    1 << (usize::BITS - 1 - (size - 1).leading_zeros())
+}
+
+/// `PATH(m, D[n])` from RFC 6962 section 2.1.1 for the subtree over
+/// `leaves`, appended to `path` leaf first. `root` gives the hash of a range
+/// of leaves.
+pub(crate) fn inclusion_path(
+    index: usize,
+    leaves: Range<usize>,
+    root: &impl Fn(Range<usize>) -> Digest,
+    path: &mut Vec<Digest>,
+) {
+    if leaves.len() <= 1 {
+        return;
+    }
+
+    let split = leaves.start + split_point(leaves.len());
+    if index < split {
+        inclusion_path(index, leaves.start..split, root, path);
+        path.push(root(split..leaves.end));
+    } else {
+        inclusion_path(index, split..leaves.end, root, path);
+        path.push(root(leaves.start..split));
+    }
+}
+
+/// `SUBPROOF(m, D[n], b)` from RFC 6962 section 2.1.2 for the subtree over
+/// `leaves`, where the old tree ends at leaf `first_size`.
+pub(crate) fn consistency_path(
+    first_size: usize,
+    leaves: Range<usize>,
+    complete: bool,
+    root: &impl Fn(Range<usize>) -> Digest,
+    path: &mut Vec<Digest>,
+) {
+    if first_size == leaves.end {
+        // The verifier knows the old root, so it only needs this subtree's
+        // hash when it's part of a larger one.
+        if !complete {
+            path.push(root(leaves));
+        }
+        return;
+    }
+
+    let split = leaves.start + split_point(leaves.len());
+    if first_size <= split {
+        consistency_path(first_size, leaves.start..split, complete, root, path);
+        path.push(root(split..leaves.end));
+    } else {
+        consistency_path(first_size, split..leaves.end, false, root, path);
+        path.push(root(leaves.start..split));
+    }
+}
+
+/// Proof that a leaf is in a tree of a given size, checked against that
+/// tree's root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionProof {
+    pub leaf_index: u64,
+    pub tree_size: u64,
+    /// Sibling hashes from the leaf up to the root.
+    pub path: Vec<Digest>,
+}
+
+impl InclusionProof {
+    /// Checks that the leaf with hash `leaf_hash` sits at `leaf_index` in the
+    /// tree of `tree_size` leaves with head `root`, following RFC 9162 section
+    /// 2.1.3.2.
+    pub fn verify(&self, leaf_hash: &Digest, root: &Digest) -> bool {
+        if self.leaf_index >= self.tree_size {
+            return false;
+        }
+
+        let mut index = self.leaf_index;
+        let mut last = self.tree_size - 1;
+        let mut hash = *leaf_hash;
+        for sibling in &self.path {
+            if last == 0 {
+                return false;
+            }
+            if index & 1 == 1 || index == last {
+                hash = node_hash(sibling, &hash);
+                // Skip levels where this node has no right sibling.
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                hash = node_hash(&hash, sibling);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+
+        last == 0 && hash == *root
+    }
+
+    /// Encodes the proof as in RFC 9162's `InclusionProofDataV2`, without the
+    /// log ID: the tree size and leaf index as big-endian `u64`s, then the
+    /// path as a vector with a 2-byte length of hashes with 1-byte lengths.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.tree_size.to_be_bytes());
+        bytes.extend_from_slice(&self.leaf_index.to_be_bytes());
+        encode_path(&self.path, &mut bytes);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseProofError> {
+        let (tree_size, bytes) = read_u64(bytes)?;
+        let (leaf_index, bytes) = read_u64(bytes)?;
+        let path = decode_path(bytes)?;
+        Ok(Self {
+            leaf_index,
+            tree_size,
+            path,
+        })
+    }
+}
+
+/// Proof that the tree of `first_size` leaves is a prefix of the tree of
+/// `second_size` leaves, checked against both trees' roots.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsistencyProof {
+    pub first_size: u64,
+    pub second_size: u64,
+    pub path: Vec<Digest>,
+}
+
+impl ConsistencyProof {
+    /// Checks that the tree with head `first_root` is a prefix of the tree
+    /// with head `second_root`, following RFC 9162 section 2.1.4.2. Every tree
+    /// extends the empty tree, so a proof from size zero only has to be
+    /// empty.
+    pub fn verify(&self, first_root: &Digest, second_root: &Digest) -> bool {
+        let (first, second) = (self.first_size, self.second_size);
+        if first > second {
+            return false;
+        }
+        if first == 0 {
+            return self.path.is_empty();
+        }
+        if first == second {
+            return self.path.is_empty() && first_root == second_root;
+        }
+
+        // When the old tree is a complete subtree, its root starts the path.
+        let mut path = self.path.iter();
+        let start = if first.is_power_of_two() {
+            first_root
+        } else {
+            match path.next() {
+                Some(hash) => hash,
+                None => return false,
+            }
+        };
+
+        let mut index = first - 1;
+        let mut last = second - 1;
+        while index & 1 == 1 {
+            index >>= 1;
+            last >>= 1;
+        }
+
+        let mut first_hash = *start;
+        let mut second_hash = *start;
+        for hash in path {
+            if last == 0 {
+                return false;
+            }
+            if index & 1 == 1 || index == last {
+                first_hash = node_hash(hash, &first_hash);
+                second_hash = node_hash(hash, &second_hash);
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                second_hash = node_hash(&second_hash, hash);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+
+        last == 0 && first_hash == *first_root && second_hash == *second_root
+    }
+
+    /// Encodes the proof as in RFC 9162's `ConsistencyProofDataV2`, without
+    /// the log ID, in the same layout as [`InclusionProof::to_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.first_size.to_be_bytes());
+        bytes.extend_from_slice(&self.second_size.to_be_bytes());
+        encode_path(&self.path, &mut bytes);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseProofError> {
+        let (first_size, bytes) = read_u64(bytes)?;
+        let (second_size, bytes) = read_u64(bytes)?;
+        let path = decode_path(bytes)?;
+        Ok(Self {
+            first_size,
+            second_size,
+            path,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseProofError {
+    /// The input ended before the proof did.
+    Truncated,
+    /// A node hash had the given length instead of 32 bytes.
+    InvalidHashLength(usize),
+    /// There were bytes left over after the proof.
+    TrailingData,
+}
+
+impl fmt::Display for ParseProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => f.write_str("proof is truncated"),
+            Self::InvalidHashLength(length) => {
+                write!(f, "invalid node hash length of {} bytes", length)
+            }
+            Self::TrailingData => f.write_str("unexpected data after the proof"),
+        }
+    }
+}
+
+impl core::error::Error for ParseProofError {}
+
+fn read_u64(bytes: &[u8]) -> Result<(u64, &[u8]), ParseProofError> {
+    let (value, rest) = bytes
+        .split_first_chunk::<8>()
+        .ok_or(ParseProofError::Truncated)?;
+    Ok((u64::from_be_bytes(*value), rest))
+}
+
+/// Writes `path` as a TLS vector with a 2-byte length, each hash prefixed by
+/// its 1-byte length.
+pub(crate) fn encode_path(path: &[Digest], bytes: &mut Vec<u8>) {
+    let length = path.len() * (1 + HASH_LENGTH);
+    bytes.extend_from_slice(&(length as u16).to_be_bytes());
+    for hash in path {
+        bytes.push(HASH_LENGTH as u8);
+        bytes.extend_from_slice(hash.as_bytes());
+    }
+}
+
+/// Reads a path written by [`encode_path`], which must be all of `bytes`.
+pub(crate) fn decode_path(bytes: &[u8]) -> Result<Vec<Digest>, ParseProofError> {
+    let (length, bytes) = bytes
+        .split_first_chunk::<2>()
+        .ok_or(ParseProofError::Truncated)?;
+    let length = usize::from(u16::from_be_bytes(*length));
+    if bytes.len() < length {
+        return Err(ParseProofError::Truncated);
+    }
+    if bytes.len() > length {
+        return Err(ParseProofError::TrailingData);
+    }
+
+    let mut path = Vec::new();
+    let mut rest = bytes;
+    while let Some((&hash_length, after)) = rest.split_first() {
+        if usize::from(hash_length) != HASH_LENGTH {
+            return Err(ParseProofError::InvalidHashLength(usize::from(hash_length)));
+        }
+        let (hash, after) = after
+            .split_first_chunk::<HASH_LENGTH>()
+            .ok_or(ParseProofError::Truncated)?;
+        path.push(Digest(*hash));
+        rest = after;
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_util::from_hex;
+
+    /// The leaves used by the Certificate Transparency reference tests.
+    pub(crate) const CT_LEAVES: [&str; 8] = [
+        "",
+        "00",
+        "10",
+        "2021",
+        "3031",
+        "40414243",
+        "5051525354555657",
+        "606162636465666768696a6b6c6d6e6f",
+    ];
+
+    pub(crate) fn ct_tree(size: usize) -> MerkleTree {
+        MerkleTree::from_entries(CT_LEAVES[..size].iter().map(|leaf| from_hex(leaf)))
+    }
+
+    pub(crate) fn digest(hex: &str) -> Digest {
+        hex.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ct_roots() {
+        let roots = [
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+            "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+            "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+            "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+        ];
+        for (size, root) in roots.iter().enumerate() {
+            assert_eq!(ct_tree(size + 1).root(), digest(root), "size {}", size + 1);
+        }
+        assert_eq!(
+            MerkleTree::new().root(),
+            digest("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+    }
+
+    #[test]
+    fn test_ct_inclusion_proofs() {
+        let proofs: [(usize, usize, &[&str]); 5] = [
+            (0, 1, &[]),
+            (
+                0,
+                8,
+                &[
+                    "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+                ],
+            ),
+            (
+                5,
+                8,
+                &[
+                    "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                    "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                    "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+                ],
+            ),
+            (
+                2,
+                3,
+                &["fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125"],
+            ),
+            (
+                1,
+                5,
+                &[
+                    "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                ],
+            ),
+        ];
+
+        for (index, size, path) in proofs {
+            let tree = ct_tree(size);
+            let proof = tree.inclusion_proof(index).unwrap();
+            let expected: Vec<Digest> = path.iter().map(|hash| digest(hash)).collect();
+            assert_eq!(proof.path, expected, "leaf {} of {}", index, size);
+            assert!(proof.verify(&tree.leaf_hashes()[index], &tree.root()));
+        }
+    }
+
+    #[test]
+    fn test_verify_every_leaf() {
+        for size in 1..=8 {
+            let tree = ct_tree(size);
+            let root = tree.root();
+            for index in 0..size {
+                let proof = tree.inclusion_proof(index).unwrap();
+                let leaf = tree.leaf_hashes()[index];
+                assert!(proof.verify(&leaf, &root));
+
+                // The proof must fail for any other leaf, root or position.
+                assert!(!proof.verify(&leaf_hash(b"other"), &root));
+                assert!(!proof.verify(&leaf, &leaf_hash(b"other")));
+                let mut moved = proof.clone();
+                moved.leaf_index = (moved.leaf_index + 1) % size as u64;
+                assert!(size == 1 || !moved.verify(&leaf, &root));
+                let mut outside = proof.clone();
+                outside.tree_size = outside.leaf_index;
+                assert!(!outside.verify(&leaf, &root));
+            }
+            assert_eq!(tree.inclusion_proof(size), None);
+        }
+    }
+
+    #[test]
+    fn test_ct_consistency_proofs() {
+        let proofs: [(usize, usize, &[&str]); 5] = [
+            (1, 1, &[]),
+            (
+                1,
+                8,
+                &[
+                    "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+                ],
+            ),
+            (
+                6,
+                8,
+                &[
+                    "0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a",
+                    "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                    "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+                ],
+            ),
+            (
+                2,
+                5,
+                &[
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                ],
+            ),
+            // Not from the CT tests: computed separately from the RFC 6962
+            // definition, for a proof starting in a right subtree.
+            (
+                3,
+                7,
+                &[
+                    "0298d122906dcfc10892cb53a73992fc5b9f493ea4c9badb27b791b4127a7fe7",
+                    "07506a85fd9dd2f120eb694f86011e5bb4662e5c415a62917033d4a9624487e7",
+                    "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+                    "837dbb152e9b079010717e84e865da4ebc0fa198a806d59d31bf15accef22d0e",
+                ],
+            ),
+        ];
+
+        for (first, second, path) in proofs {
+            let proof = ct_tree(second).consistency_proof(first).unwrap();
+            let expected: Vec<Digest> = path.iter().map(|hash| digest(hash)).collect();
+            assert_eq!(proof.path, expected, "{} to {}", first, second);
+            assert!(proof.verify(&ct_tree(first).root(), &ct_tree(second).root()));
+        }
+    }
+
+    #[test]
+    fn test_verify_every_consistency_proof() {
+        let other = leaf_hash(b"other");
+        for second in 0..=8 {
+            let tree = ct_tree(second);
+            let second_root = tree.root();
+            for first in 0..=second {
+                let proof = tree.consistency_proof(first).unwrap();
+                let first_root = ct_tree(first).root();
+                assert!(proof.verify(&first_root, &second_root));
+                if first == 0 {
+                    continue;
+                }
+
+                assert!(!proof.verify(&other, &second_root));
+                assert!(!proof.verify(&first_root, &other));
+                if first < second {
+                    let mut truncated = proof.clone();
+                    truncated.path.pop();
+                    assert!(!truncated.verify(&first_root, &second_root));
+                    let mut swapped = proof.clone();
+                    swapped.first_size = proof.second_size;
+                    swapped.second_size = proof.first_size;
+                    assert!(!swapped.verify(&second_root, &first_root));
+                }
+            }
+            assert_eq!(tree.consistency_proof(second + 1), None);
+        }
+    }
+
+    #[test]
+    fn test_leaf_and_node_are_separated() {
+        let left = leaf_hash(b"a");
+        let right = leaf_hash(b"b");
+        let mut joined = Vec::from(&left.as_bytes()[..]);
+        joined.extend_from_slice(right.as_bytes());
+        assert_ne!(node_hash(&left, &right), leaf_hash(&joined));
+    }
+
+    #[test]
+    fn test_proof_encoding() {
+        let proof = ct_tree(8).inclusion_proof(5).unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 8 + 8 + 2 + 3 * 33);
+        assert_eq!(
+            bytes[..18],
+            [0, 0, 0, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 5, 0, 99]
+        );
+        assert_eq!(InclusionProof::from_bytes(&bytes), Ok(proof));
+
+        assert_eq!(
+            InclusionProof::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ParseProofError::Truncated)
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            InclusionProof::from_bytes(&trailing),
+            Err(ParseProofError::TrailingData)
+        );
+        let mut wrong_length = bytes.clone();
+        wrong_length[18] = 31;
+        assert_eq!(
+            InclusionProof::from_bytes(&wrong_length),
+            Err(ParseProofError::InvalidHashLength(31))
+        );
+
+        let proof = ct_tree(8).consistency_proof(6).unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(
+            bytes[..18],
+            [0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 8, 0, 99]
+        );
+        assert_eq!(ConsistencyProof::from_bytes(&bytes), Ok(proof));
+        assert_eq!(
+            ConsistencyProof::from_bytes(&bytes[..12]),
+            Err(ParseProofError::Truncated)
+        );
+    }
+}
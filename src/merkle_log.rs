@@ -0,0 +1,226 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! An append-only Merkle log that keeps the hash of every complete subtree, so
+//! roots and proofs for any past size take a logarithmic number of hashes
+//! instead of rehashing the leaves.
+
+#![forbid(unsafe_code)]
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::merkle::{consistency_path, inclusion_path, leaf_hash, node_hash, split_point};
+use crate::{sha256_bytes, ConsistencyProof, Digest, InclusionProof};
+
+/// An in-memory log of leaves, with tree heads and proofs available for
+/// every size it has had. It stores about two hashes per leaf and never the
+/// leaf data itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MerkleLog {
+    /// `levels[h][i]` is the root of the complete subtree over leaves
+    /// `i << h` to `(i + 1) << h`.
+    levels: Vec<Vec<Digest>>,
+}
+
+impl MerkleLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a leaf holding `data`, returning its index.
+    pub fn append(&mut self, data: &[u8]) -> usize {
+        self.append_leaf_hash(leaf_hash(data))
+    }
+
+    /// Appends a leaf that has already been hashed with [`leaf_hash`],
+    /// returning its index.
+    pub fn append_leaf_hash(&mut self, hash: Digest) -> usize {
+        let index = self.len();
+        let mut hash = hash;
+        let mut level = 0;
+        loop {
+            if level == self.levels.len() {
+                self.levels.push(Vec::new());
+            }
+            let nodes = &mut self.levels[level];
+            nodes.push(hash);
+            // A subtree one level up is complete whenever this level has an
+            // even number of nodes.
+            if !nodes.len().is_multiple_of(2) {
+                return index;
+            }
+            hash = node_hash(&nodes[nodes.len() - 2], &nodes[nodes.len() - 1]);
+            level += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn leaf_hash(&self, index: usize) -> Option<Digest> {
+        self.levels.first()?.get(index).copied()
+    }
+
+    /// The current tree head.
+    pub fn root(&self) -> Digest {
+        self.range_root(0..self.len())
+    }
+
+    /// The tree head when the log held `size` leaves, or `None` if it hasn't
+    /// reached that size.
+    pub fn root_at(&self, size: usize) -> Option<Digest> {
+        (size <= self.len()).then(|| self.range_root(0..size))
+    }
+
+    /// Proof that leaf `index` was in the log at `tree_size`, or `None` if
+    /// the leaf or the size is out of range.
+    pub fn inclusion_proof(&self, index: usize, tree_size: usize) -> Option<InclusionProof> {
+        if index >= tree_size || tree_size > self.len() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        let root = |range| self.range_root(range);
+        inclusion_path(index, 0..tree_size, &root, &mut path);
+        Some(InclusionProof {
+            leaf_index: index as u64,
+            tree_size: tree_size as u64,
+            path,
+        })
+    }
+
+    /// Proof that the log at `first_size` is a prefix of the log at
+    /// `second_size`, or `None` if the sizes are out of order or the log
+    /// hasn't reached `second_size`.
+    pub fn consistency_proof(
+        &self,
+        first_size: usize,
+        second_size: usize,
+    ) -> Option<ConsistencyProof> {
+        if first_size > second_size || second_size > self.len() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        if first_size > 0 {
+            let root = |range| self.range_root(range);
+            consistency_path(first_size, 0..second_size, true, &root, &mut path);
+        }
+        Some(ConsistencyProof {
+            first_size: first_size as u64,
+            second_size: second_size as u64,
+            path,
+        })
+    }
+
+    /// The root of the subtree over `leaves`, built from the stored complete
+    /// subtrees. Every range reached from a tree head splits into a complete
+    /// left part and a smaller right part, so this recurses once per level.
+    fn range_root(&self, leaves: Range<usize>) -> Digest {
+        let size = leaves.len();
+        if size == 0 {
+            return sha256_bytes(&[]);
+        }
+        if size.is_power_of_two() && leaves.start.is_multiple_of(size) {
+            let level = size.trailing_zeros() as usize;
+            return self.levels[level][leaves.start >> level];
+        }
+
+        let split = leaves.start + split_point(size);
+        node_hash(
+            &self.range_root(leaves.start..split),
+            &self.range_root(split..leaves.end),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::tests::{ct_tree, CT_LEAVES};
+    use crate::test_util::from_hex;
+    use crate::MerkleTree;
+    use alloc::vec;
+
+    fn entry(index: usize) -> [u8; 8] {
+        (index as u64).to_be_bytes()
+    }
+
+    #[test]
+    fn test_matches_ct_trees() {
+        let mut log = MerkleLog::new();
+        assert_eq!(log.root(), MerkleTree::new().root());
+        for (index, leaf) in CT_LEAVES.iter().enumerate() {
+            assert_eq!(log.append(&from_hex(leaf)), index);
+        }
+
+        for size in 0..=CT_LEAVES.len() {
+            let tree = ct_tree(size);
+            assert_eq!(log.root_at(size), Some(tree.root()));
+            for index in 0..size {
+                assert_eq!(
+                    log.inclusion_proof(index, size),
+                    tree.inclusion_proof(index)
+                );
+            }
+            for first in 0..=size {
+                assert_eq!(
+                    log.consistency_proof(first, size),
+                    tree.consistency_proof(first)
+                );
+            }
+        }
+        assert_eq!(log.root_at(CT_LEAVES.len() + 1), None);
+    }
+
+    #[test]
+    fn test_proofs_at_every_size() {
+        let mut log = MerkleLog::new();
+        let mut tree = MerkleTree::new();
+        let mut roots = vec![log.root()];
+        for index in 0..70 {
+            log.append(&entry(index));
+            tree.push(&entry(index));
+            assert_eq!(log.root(), tree.root());
+            roots.push(log.root());
+        }
+
+        for size in [1, 2, 31, 32, 33, 64, 70] {
+            for index in 0..size {
+                let proof = log.inclusion_proof(index, size).unwrap();
+                let leaf = log.leaf_hash(index).unwrap();
+                assert_eq!(leaf, leaf_hash(&entry(index)));
+                assert!(proof.verify(&leaf, &roots[size]));
+            }
+            for first in 0..=size {
+                let proof = log.consistency_proof(first, size).unwrap();
+                assert!(proof.verify(&roots[first], &roots[size]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let mut log = MerkleLog::new();
+        assert!(log.is_empty());
+        assert_eq!(log.leaf_hash(0), None);
+        for index in 0..5 {
+            log.append(&entry(index));
+        }
+
+        assert_eq!(log.len(), 5);
+        assert_eq!(log.inclusion_proof(5, 5), None);
+        assert_eq!(log.inclusion_proof(2, 6), None);
+        assert_eq!(log.inclusion_proof(3, 3), None);
+        assert_eq!(log.consistency_proof(4, 3), None);
+        assert_eq!(log.consistency_proof(3, 6), None);
+    }
+}
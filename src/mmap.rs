@@ -0,0 +1,132 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Hashing files through a read-only memory map. A mapping is only sound
+//! while no process modifies or truncates the file, which this crate can't
+//! enforce, so the entry point is `unsafe` and [`hash_file`](crate::hash_file)
+//! never maps.
+
+#![allow(unsafe_code)]
+
+use std::fs::File;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use crate::{hash_reader, Digest, Sha256};
+
+/// How much of a mapped file is passed to the hasher at once.
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Hashes the file at `path` by memory mapping it, which avoids copying the
+/// contents through a read buffer. Files that can't be mapped, such as empty
+/// files, pipes and files on filesystems without `mmap` support, are read
+/// instead.
+///
+/// # Safety
+///
+/// Nothing, in this process or any other, may write to or truncate the file
+/// until this returns. The mapping shares the file's pages, so changes show up
+/// as bytes changing under a `&[u8]`, and reading past a truncated end raises
+/// `SIGBUS`.
+pub unsafe fn hash_file_mmap(path: impl AsRef<Path>) -> io::Result<Digest> {
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let length = usize::try_from(metadata.len()).unwrap_or(0);
+    if !metadata.is_file() || length == 0 {
+        return hash_reader(file);
+    }
+
+    // SAFETY: the caller guarantees the file doesn't change while mapped.
+    let Ok(map) = (unsafe { Mmap::map(&file, length) }) else {
+        return hash_reader(file);
+    };
+    let mut hasher = Sha256::new();
+    for chunk in map.as_slice().chunks(CHUNK_SIZE) {
+        hasher.try_update(chunk).map_err(io::Error::other)?;
+    }
+    Ok(hasher.finalize())
+}
+
+struct Mmap {
+    address: *mut libc::c_void,
+    length: usize,
+}
+
+impl Mmap {
+    /// Maps the first `length` bytes of `file`, which must not be zero.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be written to or truncated while the map is alive.
+    unsafe fn map(file: &File, length: usize) -> io::Result<Self> {
+        // SAFETY: a fresh private read-only mapping doesn't alias any Rust
+        // memory, and failure is reported through the return value.
+        let address = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                length,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if address == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { address, length })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        // SAFETY: the mapping is `length` readable bytes, lives until drop,
+        // and `map`'s caller keeps the file from changing underneath it.
+        unsafe { slice::from_raw_parts(self.address as *const u8, self.length) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        // SAFETY: the mapping came from `mmap` and no slices outlive `self`.
+        unsafe { libc::munmap(self.address, self.length) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::tests::{random_bytes, TempFile};
+    use crate::sha256_bytes;
+
+    #[test]
+    fn test_hash_file_mmap() {
+        for (name, length) in [
+            ("mmap-empty", 0),
+            ("mmap-small", 100),
+            ("mmap-large", 3 << 20),
+        ] {
+            let contents = random_bytes(length);
+            let file = TempFile::new(name, &contents);
+            // SAFETY: the file is private to this test.
+            let digest = unsafe { hash_file_mmap(&file.0) }.unwrap();
+            assert_eq!(digest, sha256_bytes(&contents), "{} bytes", length);
+        }
+
+        // sysfs attributes are regular files that refuse to be mapped.
+        let attribute = "/sys/kernel/profiling";
+        if Path::new(attribute).is_file() {
+            // SAFETY: the attribute is only read.
+            let digest = unsafe { hash_file_mmap(attribute) }.unwrap();
+            assert_eq!(digest, crate::hash_file(attribute).unwrap());
+        }
+
+        // SAFETY: there is no file to change.
+        let missing = unsafe { hash_file_mmap("/nonexistent/sha256-rust") };
+        assert_eq!(missing.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
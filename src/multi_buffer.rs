@@ -0,0 +1,268 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Hashing many independent messages at once. Each SIMD lane holds the state
+//! of a different message, so 4, 8 or 16 messages advance by one block per
+//! vectorised compression. Lanes are refilled with the next message as soon as
+//! theirs is finished, which keeps them busy when message lengths differ.
+
+#![forbid(unsafe_code)]
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+use crate::{get_digest, SQRT_CONST};
+use crate::{sha256_bytes, Digest};
+
+/// Hashes every message in `messages`, returning the digests in the same
+/// order. Uses AVX-512, the SHA extensions or AVX2 when available and falls
+/// back to hashing the messages one at a time otherwise.
+pub fn hash_many(messages: &[&[u8]]) -> Vec<Digest> {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        use crate::multi_buffer_x86::{avx2, avx512, sse2};
+
+        // Sixteen AVX-512 lanes outrun one SHA-NI stream once they are all
+        // busy, but four SSE2 or eight AVX2 lanes don't; see the
+        // `bench_backends` test.
+        if messages.len() >= 16 {
+            if let Some(avx512) = avx512::Backend::detect() {
+                return hash_lanes(messages, |hashes, blocks| avx512.compress(hashes, blocks));
+            }
+        }
+        if !crate::sha_ni::is_supported() {
+            if let Some(avx2) = avx2::Backend::detect() {
+                return hash_lanes(messages, |hashes, blocks| avx2.compress(hashes, blocks));
+            }
+            if let Some(sse2) = sse2::Backend::detect() {
+                return hash_lanes(messages, |hashes, blocks| sse2.compress(hashes, blocks));
+            }
+        }
+    }
+
+    messages
+        .iter()
+        .map(|message| sha256_bytes(message))
+        .collect()
+}
+
+/// A message split into the full blocks it can be read from directly and a
+/// padded tail of one or two blocks.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+struct Job<'a> {
+    index: usize,
+    message: &'a [u8],
+    tail: [u8; 128],
+    blocks: usize,
+    next_block: usize,
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+impl<'a> Job<'a> {
+    fn new(index: usize, message: &'a [u8]) -> Self {
+        let full_blocks = message.len() / 64;
+        let remainder = &message[full_blocks * 64..];
+
+        let mut tail = [0u8; 128];
+        tail[..remainder.len()].copy_from_slice(remainder);
+        tail[remainder.len()] = 0x80;
+        let tail_blocks = if remainder.len() < 56 { 1 } else { 2 };
+        let bit_length = (message.len() as u64).wrapping_mul(8);
+        tail[tail_blocks * 64 - 8..tail_blocks * 64].copy_from_slice(&bit_length.to_be_bytes());
+
+        Self {
+            index,
+            message: &message[..full_blocks * 64],
+            tail,
+            blocks: full_blocks + tail_blocks,
+            next_block: 0,
+        }
+    }
+
+    fn block(&self, block: usize) -> &[u8] {
+        let offset = block * 64;
+        if offset < self.message.len() {
+            &self.message[offset..offset + 64]
+        } else {
+            let offset = offset - self.message.len();
+            &self.tail[offset..offset + 64]
+        }
+    }
+}
+
+/// Runs `compress` over the messages `LANES` at a time. The state is kept
+/// transposed, so `hashes[i]` holds word `i` of every lane and the backends
+/// can load and store it without shuffling.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn hash_lanes<const LANES: usize>(
+    messages: &[&[u8]],
+    compress: impl Fn(&mut [[u32; LANES]; 8], &[[u8; 64]; LANES]),
+) -> Vec<Digest> {
+    let mut digests = vec![Digest([0; 32]); messages.len()];
+    let mut pending = messages.iter().enumerate();
+    let mut lanes: [Option<Job>; LANES] = core::array::from_fn(|_| None);
+    let mut hashes = SQRT_CONST.map(|word| [word; LANES]);
+    let mut blocks = [[0u8; 64]; LANES];
+
+    loop {
+        let mut active = false;
+        for (lane, job) in lanes.iter_mut().enumerate() {
+            if job.is_none() {
+                if let Some((index, message)) = pending.next() {
+                    *job = Some(Job::new(index, message));
+                    for (words, initial) in hashes.iter_mut().zip(SQRT_CONST) {
+                        words[lane] = initial;
+                    }
+                }
+            }
+            // Idle lanes compress whatever is left in their block buffer and
+            // the result is discarded.
+            if let Some(job) = job {
+                blocks[lane].copy_from_slice(job.block(job.next_block));
+                active = true;
+            }
+        }
+        if !active {
+            break;
+        }
+
+        compress(&mut hashes, &blocks);
+
+        for (lane, slot) in lanes.iter_mut().enumerate() {
+            if let Some(job) = slot {
+                job.next_block += 1;
+                if job.next_block == job.blocks {
+                    let hash = core::array::from_fn(|i| hashes[i][lane]);
+                    digests[job.index] = get_digest(&hash);
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    digests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TestRng;
+    use alloc::vec;
+
+    fn messages() -> Vec<Vec<u8>> {
+        let mut rng = TestRng(0x2545f4914f6cdd1d);
+        let mut messages: Vec<Vec<u8>> = (0..200).map(|length| vec![0xa5; length]).collect();
+        for _ in 0..37 {
+            let mut message = vec![0u8; (rng.next() % 1000) as usize];
+            rng.fill(&mut message);
+            messages.push(message);
+        }
+        messages
+    }
+
+    fn assert_matches_sha256(digests: &[Digest], messages: &[Vec<u8>]) {
+        assert_eq!(digests.len(), messages.len());
+        for (digest, message) in digests.iter().zip(messages) {
+            assert_eq!(*digest, sha256_bytes(message), "length {}", message.len());
+        }
+    }
+
+    #[test]
+    fn test_hash_many() {
+        let messages = messages();
+        let slices: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+        assert_matches_sha256(&hash_many(&slices), &messages);
+        assert!(hash_many(&[]).is_empty());
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    #[test]
+    fn test_backends_match_sha256() {
+        use crate::multi_buffer_x86::{avx2, avx512, sse2};
+
+        let messages = messages();
+        let slices: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+
+        if let Some(sse2) = sse2::Backend::detect() {
+            let digests = hash_lanes(&slices, |h, b| sse2.compress(h, b));
+            assert_matches_sha256(&digests, &messages);
+        }
+        if let Some(avx2) = avx2::Backend::detect() {
+            let digests = hash_lanes(&slices, |h, b| avx2.compress(h, b));
+            assert_matches_sha256(&digests, &messages);
+        }
+        if let Some(avx512) = avx512::Backend::detect() {
+            let digests = hash_lanes(&slices, |h, b| avx512.compress(h, b));
+            assert_matches_sha256(&digests, &messages);
+        }
+    }
+
+    /// Compares each lane backend with hashing the messages one at a time,
+    /// which uses the SHA extensions when the CPU has them. The dispatch in
+    /// [`hash_many`] follows these numbers, so rerun it when changing either
+    /// side:
+    ///
+    /// `cargo test --release --lib bench_backends -- --ignored --nocapture`
+    #[cfg(all(feature = "std", feature = "simd", target_arch = "x86_64"))]
+    #[test]
+    #[ignore]
+    fn bench_backends() {
+        use crate::multi_buffer_x86::{avx2, avx512, sse2};
+        use std::hint::black_box;
+        use std::time::{Duration, Instant};
+
+        fn measure(name: &str, bytes: usize, hash: impl Fn() -> Vec<Digest>) {
+            let mut best = Duration::MAX;
+            for _ in 0..10 {
+                let start = Instant::now();
+                black_box(hash());
+                best = best.min(start.elapsed());
+            }
+            let rate = bytes as f64 / best.as_secs_f64() / 1e6;
+            std::println!("  {:<12} {:>9.2?} {:>8.0} MB/s", name, best, rate);
+        }
+
+        let mut rng = TestRng(0x9e3779b97f4a7c15);
+        for (count, length) in [(16384, 64), (4096, 1024), (256, 16384), (8, 65536)] {
+            let messages: Vec<Vec<u8>> = (0..count)
+                .map(|_| {
+                    let mut message = vec![0u8; length];
+                    rng.fill(&mut message);
+                    message
+                })
+                .collect();
+            let slices: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+            let slices = slices.as_slice();
+            let bytes = count * length;
+
+            std::println!("{} messages of {} bytes:", count, length);
+            let name = if crate::sha_ni::is_supported() {
+                "sha-ni"
+            } else {
+                "sequential"
+            };
+            measure(name, bytes, || {
+                slices.iter().map(|message| sha256_bytes(message)).collect()
+            });
+            if let Some(sse2) = sse2::Backend::detect() {
+                measure("sse2 x4", bytes, || {
+                    hash_lanes(slices, |h, b| sse2.compress(h, b))
+                });
+            }
+            if let Some(avx2) = avx2::Backend::detect() {
+                measure("avx2 x8", bytes, || {
+                    hash_lanes(slices, |h, b| avx2.compress(h, b))
+                });
+            }
+            if let Some(avx512) = avx512::Backend::detect() {
+                measure("avx512 x16", bytes, || {
+                    hash_lanes(slices, |h, b| avx512.compress(h, b))
+                });
+            }
+        }
+    }
+}
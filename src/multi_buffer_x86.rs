@@ -0,0 +1,363 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! SSE2, AVX2 and AVX-512 compression functions for
+//! [`hash_many`](crate::hash_many), each advancing 4, 8 or 16 independent
+//! states by one block. This is the only place the multi-buffer code uses
+//! intrinsics; the lane scheduling lives in the portable `multi_buffer`
+//! module and reaches the backends through their safe [`Backend`] handles.
+//!
+//! [`Backend`]: avx2::Backend
+
+#![allow(unsafe_code)]
+
+/// Generates a multi-buffer compression function for one x86_64 vector type.
+/// The state and message schedule are kept transposed, so vector `i` holds
+/// word `i` of every lane and the round functions operate on all lanes at
+/// once. Each backend supplies `load_message`, which transposes and
+/// byte-swaps the lanes' blocks into the first sixteen schedule words.
+macro_rules! multi_buffer_backend {
+    (
+        $module:ident,
+        $feature:tt,
+        $vector:ty,
+        $lanes:literal,
+        load: $load:ident,
+        store: $store:ident,
+        splat: $splat:ident,
+        add: $add:ident,
+        and: $and:ident,
+        andnot: $andnot:ident,
+        or: $or:ident,
+        xor: $xor:ident,
+        srl: $srl:ident,
+        sll: $sll:ident,
+        $($load_message:item)*
+    ) => {
+        pub(crate) mod $module {
+            use core::arch::x86_64::*;
+
+            use crate::CBRT_CONST;
+
+            const LANES: usize = $lanes;
+
+            #[cfg(feature = "std")]
+            fn is_supported() -> bool {
+                std::is_x86_feature_detected!($feature)
+            }
+
+            #[cfg(not(feature = "std"))]
+            fn is_supported() -> bool {
+                cfg!(target_feature = $feature)
+            }
+
+            /// Handle to the backend, only obtainable on CPUs that support it.
+            #[derive(Clone, Copy)]
+            pub(crate) struct Backend(());
+
+            impl Backend {
+                pub(crate) fn detect() -> Option<Self> {
+                    is_supported().then_some(Self(()))
+                }
+
+                /// Compresses one block per lane into the transposed state.
+                pub(crate) fn compress(
+                    self,
+                    hashes: &mut [[u32; LANES]; 8],
+                    blocks: &[[u8; 64]; LANES],
+                ) {
+                    // SAFETY: `detect` checked that the CPU supports the
+                    // target feature.
+                    unsafe { compress(hashes, blocks) }
+                }
+            }
+
+            $($load_message)*
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn add(a: $vector, b: $vector) -> $vector {
+                $add(a, b)
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn shr(x: $vector, n: i32) -> $vector {
+                $srl(x, _mm_cvtsi32_si128(n))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn rotr(x: $vector, n: i32) -> $vector {
+                $or(shr(x, n), $sll(x, _mm_cvtsi32_si128(32 - n)))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn xor3(a: $vector, b: $vector, c: $vector) -> $vector {
+                $xor($xor(a, b), c)
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn sig0(x: $vector) -> $vector {
+                xor3(rotr(x, 7), rotr(x, 18), shr(x, 3))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn sig1(x: $vector) -> $vector {
+                xor3(rotr(x, 17), rotr(x, 19), shr(x, 10))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn usig0(x: $vector) -> $vector {
+                xor3(rotr(x, 2), rotr(x, 13), rotr(x, 22))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn usig1(x: $vector) -> $vector {
+                xor3(rotr(x, 6), rotr(x, 11), rotr(x, 25))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn ch(x: $vector, y: $vector, z: $vector) -> $vector {
+                $xor($and(x, y), $andnot(x, z))
+            }
+
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn maj(x: $vector, y: $vector, z: $vector) -> $vector {
+                xor3($and(x, y), $and(x, z), $and(y, z))
+            }
+
+            #[target_feature(enable = $feature)]
+            unsafe fn create_message_schedule(blocks: &[[u8; 64]; LANES]) -> [$vector; 64] {
+                let mut schedule = [$splat(0); 64];
+                schedule[..16].copy_from_slice(&load_message(blocks));
+
+                for i in 16..64 {
+                    schedule[i] = add(
+                        add(sig1(schedule[i - 2]), schedule[i - 7]),
+                        add(sig0(schedule[i - 15]), schedule[i - 16]),
+                    );
+                }
+
+                schedule
+            }
+
+            /// # Safety
+            ///
+            /// The CPU must support the backend's target feature, see
+            /// [`is_supported`].
+            #[target_feature(enable = $feature)]
+            unsafe fn compress(
+                hashes: &mut [[u32; LANES]; 8],
+                blocks: &[[u8; 64]; LANES],
+            ) {
+                let schedule = create_message_schedule(blocks);
+
+                let mut initial = [$splat(0); 8];
+                for (word, lanes) in initial.iter_mut().zip(hashes.iter()) {
+                    *word = $load(lanes.as_ptr().cast());
+                }
+                let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = initial;
+
+                for i in 0..64 {
+                    let temp1 = add(
+                        add(usig1(e), ch(e, f, g)),
+                        add(h, add($splat(CBRT_CONST[i] as i32), schedule[i])),
+                    );
+                    let temp2 = add(usig0(a), maj(a, b, c));
+
+                    h = g;
+                    g = f;
+                    f = e;
+                    e = add(d, temp1);
+                    d = c;
+                    c = b;
+                    b = a;
+                    a = add(temp1, temp2);
+                }
+
+                let registers = [a, b, c, d, e, f, g, h];
+                for (lanes, (initial, register)) in hashes.iter_mut().zip(initial.iter().zip(registers)) {
+                    $store(lanes.as_mut_ptr().cast(), add(*initial, register));
+                }
+            }
+        }
+    };
+}
+
+multi_buffer_backend!(
+    sse2,
+    "sse2",
+    __m128i,
+    4,
+    load: _mm_loadu_si128,
+    store: _mm_storeu_si128,
+    splat: _mm_set1_epi32,
+    add: _mm_add_epi32,
+    and: _mm_and_si128,
+    andnot: _mm_andnot_si128,
+    or: _mm_or_si128,
+    xor: _mm_xor_si128,
+    srl: _mm_srl_epi32,
+    sll: _mm_sll_epi32,
+
+    /// Transposes each 4x4 tile of words with unpacks. SSE2 has no byte
+    /// shuffle, so words are byte-swapped by swapping their halves and then
+    /// the bytes within each half.
+    #[target_feature(enable = "sse2")]
+    #[inline]
+    unsafe fn load_message(blocks: &[[u8; 64]; LANES]) -> [__m128i; 16] {
+        let mut words = [_mm_setzero_si128(); 16];
+        for (tile, words) in words.chunks_exact_mut(4).enumerate() {
+            let row = |lane: usize| _mm_loadu_si128(blocks[lane][tile * 16..].as_ptr().cast());
+            let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+
+            let t0 = _mm_unpacklo_epi32(r0, r1);
+            let t1 = _mm_unpacklo_epi32(r2, r3);
+            let t2 = _mm_unpackhi_epi32(r0, r1);
+            let t3 = _mm_unpackhi_epi32(r2, r3);
+            let transposed = [
+                _mm_unpacklo_epi64(t0, t1),
+                _mm_unpackhi_epi64(t0, t1),
+                _mm_unpacklo_epi64(t2, t3),
+                _mm_unpackhi_epi64(t2, t3),
+            ];
+
+            for (word, x) in words.iter_mut().zip(transposed) {
+                let x = _mm_or_si128(_mm_slli_epi32::<16>(x), _mm_srli_epi32::<16>(x));
+                *word = _mm_or_si128(_mm_slli_epi16::<8>(x), _mm_srli_epi16::<8>(x));
+            }
+        }
+        words
+    }
+);
+
+multi_buffer_backend!(
+    avx2,
+    "avx2",
+    __m256i,
+    8,
+    load: _mm256_loadu_si256,
+    store: _mm256_storeu_si256,
+    splat: _mm256_set1_epi32,
+    add: _mm256_add_epi32,
+    and: _mm256_and_si256,
+    andnot: _mm256_andnot_si256,
+    or: _mm256_or_si256,
+    xor: _mm256_xor_si256,
+    srl: _mm256_srl_epi32,
+    sll: _mm256_sll_epi32,
+
+    /// Transposes each 8x8 tile of words: unpacks interleave pairs of rows
+    /// within the 128-bit halves, then `permute2x128` joins the halves.
+    #[target_feature(enable = "avx2")]
+    #[inline]
+    unsafe fn load_message(blocks: &[[u8; 64]; LANES]) -> [__m256i; 16] {
+        let byte_swap = _mm256_set_epi8(
+            12, 13, 14, 15, 8, 9, 10, 11, 4, 5, 6, 7, 0, 1, 2, 3, 12, 13, 14, 15, 8, 9, 10, 11, 4,
+            5, 6, 7, 0, 1, 2, 3,
+        );
+
+        let mut words = [_mm256_setzero_si256(); 16];
+        for (tile, words) in words.chunks_exact_mut(8).enumerate() {
+            let rows: [__m256i; 8] = core::array::from_fn(|lane| {
+                _mm256_loadu_si256(blocks[lane][tile * 32..].as_ptr().cast())
+            });
+
+            let mut pairs = [_mm256_setzero_si256(); 8];
+            for (i, pair) in rows.chunks_exact(2).enumerate() {
+                pairs[i * 2] = _mm256_unpacklo_epi32(pair[0], pair[1]);
+                pairs[i * 2 + 1] = _mm256_unpackhi_epi32(pair[0], pair[1]);
+            }
+            // `quads[q * 4 + w]` holds word `w` of lanes `4q..4q + 4` in its
+            // low half and word `w + 4` in its high half.
+            let mut quads = [_mm256_setzero_si256(); 8];
+            for (q, pairs) in pairs.chunks_exact(4).enumerate() {
+                quads[q * 4] = _mm256_unpacklo_epi64(pairs[0], pairs[2]);
+                quads[q * 4 + 1] = _mm256_unpackhi_epi64(pairs[0], pairs[2]);
+                quads[q * 4 + 2] = _mm256_unpacklo_epi64(pairs[1], pairs[3]);
+                quads[q * 4 + 3] = _mm256_unpackhi_epi64(pairs[1], pairs[3]);
+            }
+
+            for w in 0..4 {
+                let (low, high) = (quads[w], quads[w + 4]);
+                words[w] = _mm256_shuffle_epi8(_mm256_permute2x128_si256::<0x20>(low, high), byte_swap);
+                words[w + 4] = _mm256_shuffle_epi8(_mm256_permute2x128_si256::<0x31>(low, high), byte_swap);
+            }
+        }
+        words
+    }
+);
+
+multi_buffer_backend!(
+    avx512,
+    "avx512f",
+    __m512i,
+    16,
+    load: _mm512_loadu_si512,
+    store: _mm512_storeu_si512,
+    splat: _mm512_set1_epi32,
+    add: _mm512_add_epi32,
+    and: _mm512_and_si512,
+    andnot: _mm512_andnot_si512,
+    or: _mm512_or_si512,
+    xor: _mm512_xor_si512,
+    srl: _mm512_srl_epi32,
+    sll: _mm512_sll_epi32,
+
+    /// Transposes the 16x16 words with unpacks inside each 128-bit lane and
+    /// then a 4x4 transpose of the 128-bit lanes. AVX-512F has no byte
+    /// shuffle, so words are byte-swapped with masks and rotates.
+    #[target_feature(enable = "avx512f")]
+    #[inline]
+    unsafe fn load_message(blocks: &[[u8; 64]; LANES]) -> [__m512i; 16] {
+        let rows: [__m512i; 16] =
+            core::array::from_fn(|lane| _mm512_loadu_si512(blocks[lane].as_ptr().cast()));
+
+        let mut pairs = [_mm512_setzero_si512(); 16];
+        for (i, pair) in rows.chunks_exact(2).enumerate() {
+            pairs[i * 2] = _mm512_unpacklo_epi32(pair[0], pair[1]);
+            pairs[i * 2 + 1] = _mm512_unpackhi_epi32(pair[0], pair[1]);
+        }
+        // 128-bit lane `l` of `quads[q * 4 + w]` holds word `4l + w` of
+        // lanes `4q..4q + 4`.
+        let mut quads = [_mm512_setzero_si512(); 16];
+        for (q, pairs) in pairs.chunks_exact(4).enumerate() {
+            quads[q * 4] = _mm512_unpacklo_epi64(pairs[0], pairs[2]);
+            quads[q * 4 + 1] = _mm512_unpackhi_epi64(pairs[0], pairs[2]);
+            quads[q * 4 + 2] = _mm512_unpacklo_epi64(pairs[1], pairs[3]);
+            quads[q * 4 + 3] = _mm512_unpackhi_epi64(pairs[1], pairs[3]);
+        }
+
+        let low_bytes = _mm512_set1_epi32(0x00ff_00ff);
+        let byte_swap = |x| {
+            _mm512_or_si512(
+                _mm512_ror_epi32::<8>(_mm512_and_si512(x, low_bytes)),
+                _mm512_rol_epi32::<8>(_mm512_andnot_si512(low_bytes, x)),
+            )
+        };
+
+        let mut words = [_mm512_setzero_si512(); 16];
+        for w in 0..4 {
+            let (a, b, c, d) = (quads[w], quads[w + 4], quads[w + 8], quads[w + 12]);
+            let ab_low = _mm512_shuffle_i32x4::<0x44>(a, b);
+            let ab_high = _mm512_shuffle_i32x4::<0xee>(a, b);
+            let cd_low = _mm512_shuffle_i32x4::<0x44>(c, d);
+            let cd_high = _mm512_shuffle_i32x4::<0xee>(c, d);
+            words[w] = byte_swap(_mm512_shuffle_i32x4::<0x88>(ab_low, cd_low));
+            words[w + 4] = byte_swap(_mm512_shuffle_i32x4::<0xdd>(ab_low, cd_low));
+            words[w + 8] = byte_swap(_mm512_shuffle_i32x4::<0x88>(ab_high, cd_high));
+            words[w + 12] = byte_swap(_mm512_shuffle_i32x4::<0xdd>(ab_high, cd_high));
+        }
+        words
+    }
+);
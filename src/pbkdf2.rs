@@ -0,0 +1,408 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! PBKDF2-HMAC-SHA256 (RFC 8018) and the PHC string format used to store its
+//! output, `$pbkdf2-sha256$i=<iterations>$<salt>$<hash>`.
+
+#![forbid(unsafe_code)]
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt;
+#[cfg(feature = "alloc")]
+use core::str::FromStr;
+
+#[cfg(feature = "alloc")]
+use crate::digest::constant_time_eq;
+use crate::Hmac;
+
+#[cfg(feature = "alloc")]
+const ALGORITHM: &str = "pbkdf2-sha256";
+#[cfg(feature = "alloc")]
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// RFC 8018 §5.2 numbers blocks with a 32-bit counter, so a derived key is at
+/// most `2^32 - 1` blocks long.
+const MAX_OUTPUT_LENGTH: u64 = u32::MAX as u64 * 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pbkdf2Error {
+    /// The iteration count was zero.
+    InvalidIterations,
+    /// The requested output length was zero.
+    InvalidOutputLength,
+    /// The requested output length was more than `(2^32 - 1) * 32` bytes.
+    OutputTooLong,
+    /// A PHC string named an algorithm other than `pbkdf2-sha256`.
+    UnsupportedAlgorithm,
+    /// A PHC string did not have the expected fields.
+    InvalidFormat,
+    /// A salt or hash field was not valid unpadded base64.
+    InvalidBase64,
+}
+
+impl fmt::Display for Pbkdf2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::InvalidIterations => "iteration count must be at least 1",
+            Self::InvalidOutputLength => "output length must be at least 1",
+            Self::OutputTooLong => "derived key too long",
+            Self::UnsupportedAlgorithm => "unsupported password hash algorithm",
+            Self::InvalidFormat => "malformed PHC string",
+            Self::InvalidBase64 => "invalid base64 in PHC string",
+        };
+        f.write_str(message)
+    }
+}
+
+impl core::error::Error for Pbkdf2Error {}
+
+/// Fills `output` with the PBKDF2-HMAC-SHA256 derived key.
+pub fn pbkdf2_sha256(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    output: &mut [u8],
+) -> Result<(), Pbkdf2Error> {
+    check_parameters(iterations, output.len())?;
+
+    // The password only keys the HMAC once; every iteration starts from a
+    // clone of the precomputed inner and outer states.
+    let keyed = Hmac::new(password);
+
+    for (block_index, chunk) in (1..=u32::MAX).zip(output.chunks_mut(32)) {
+        let mut hmac = keyed.clone();
+        hmac.update(salt);
+        hmac.update(&block_index.to_be_bytes());
+
+        let mut u = hmac.finalize();
+        let mut block = *u.as_bytes();
+
+        for _ in 1..iterations {
+            let mut hmac = keyed.clone();
+            hmac.update(u.as_bytes());
+            u = hmac.finalize();
+
+            for (accumulated, byte) in block.iter_mut().zip(u.as_bytes()) {
+                *accumulated ^= byte;
+            }
+        }
+
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+
+    Ok(())
+}
+
+fn check_parameters(iterations: u32, length: usize) -> Result<(), Pbkdf2Error> {
+    if iterations == 0 {
+        return Err(Pbkdf2Error::InvalidIterations);
+    }
+    if length == 0 {
+        return Err(Pbkdf2Error::InvalidOutputLength);
+    }
+    if length as u64 > MAX_OUTPUT_LENGTH {
+        return Err(Pbkdf2Error::OutputTooLong);
+    }
+    Ok(())
+}
+
+/// A PBKDF2-HMAC-SHA256 password hash along with the parameters needed to
+/// verify it. `Display` and `FromStr` convert to and from PHC strings.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pbkdf2Hash {
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl Pbkdf2Hash {
+    /// Hashes `password` into `length` bytes. The salt should be unique per
+    /// password and come from a secure random source.
+    pub fn new(
+        password: &[u8],
+        salt: &[u8],
+        iterations: u32,
+        length: usize,
+    ) -> Result<Self, Pbkdf2Error> {
+        let mut hash = vec![0; length];
+        pbkdf2_sha256(password, salt, iterations, &mut hash)?;
+
+        Ok(Self {
+            iterations,
+            salt: salt.to_vec(),
+            hash,
+        })
+    }
+
+    /// Returns whether `password` produces this hash, comparing in constant
+    /// time.
+    pub fn verify(&self, password: &[u8]) -> bool {
+        let mut candidate = vec![0; self.hash.len()];
+        if pbkdf2_sha256(password, &self.salt, self.iterations, &mut candidate).is_err() {
+            return false;
+        }
+        constant_time_eq(&candidate, &self.hash)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for Pbkdf2Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "${}$i={}${}${}",
+            ALGORITHM,
+            self.iterations,
+            encode_base64(&self.salt),
+            encode_base64(&self.hash)
+        )
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FromStr for Pbkdf2Hash {
+    type Err = Pbkdf2Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split('$');
+        if fields.next() != Some("") {
+            return Err(Pbkdf2Error::InvalidFormat);
+        }
+        if fields.next().ok_or(Pbkdf2Error::InvalidFormat)? != ALGORITHM {
+            return Err(Pbkdf2Error::UnsupportedAlgorithm);
+        }
+
+        let (Some(params), Some(salt), Some(hash), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(Pbkdf2Error::InvalidFormat);
+        };
+
+        // PHC decimal values have no sign and no leading zeros.
+        let iterations = params
+            .strip_prefix("i=")
+            .filter(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+            .filter(|digits| *digits == "0" || !digits.starts_with('0'))
+            .and_then(|digits| digits.parse().ok())
+            .ok_or(Pbkdf2Error::InvalidFormat)?;
+        if iterations == 0 {
+            return Err(Pbkdf2Error::InvalidIterations);
+        }
+
+        let salt = decode_base64(salt)?;
+        let hash = decode_base64(hash)?;
+        if hash.is_empty() {
+            return Err(Pbkdf2Error::InvalidOutputLength);
+        }
+
+        Ok(Self {
+            iterations,
+            salt,
+            hash,
+        })
+    }
+}
+
+/// Checks `password` against a `$pbkdf2-sha256$...` PHC string.
+#[cfg(feature = "alloc")]
+pub fn verify_pbkdf2_sha256(password: &[u8], phc: &str) -> Result<bool, Pbkdf2Error> {
+    let stored: Pbkdf2Hash = phc.parse()?;
+    Ok(stored.verify(password))
+}
+
+/// Standard base64 without padding, as required by the PHC string format.
+#[cfg(feature = "alloc")]
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() * 4).div_ceil(3));
+
+    for chunk in bytes.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from(group[0]) << 16 | u32::from(group[1]) << 8 | u32::from(group[2]);
+
+        for i in 0..=chunk.len() {
+            let index = (bits >> (18 - 6 * i)) & 0x3f;
+            encoded.push(BASE64_ALPHABET[index as usize] as char);
+        }
+    }
+
+    encoded
+}
+
+#[cfg(feature = "alloc")]
+fn decode_base64(encoded: &str) -> Result<Vec<u8>, Pbkdf2Error> {
+    if encoded.len() % 4 == 1 {
+        return Err(Pbkdf2Error::InvalidBase64);
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() * 3 / 4);
+    for chunk in encoded.as_bytes().chunks(4) {
+        let mut bits = 0u32;
+        for (i, &character) in chunk.iter().enumerate() {
+            let value = BASE64_ALPHABET
+                .iter()
+                .position(|&c| c == character)
+                .ok_or(Pbkdf2Error::InvalidBase64)?;
+            bits |= (value as u32) << (18 - 6 * i);
+        }
+
+        let bytes = bits.to_be_bytes();
+        let length = chunk.len() - 1;
+        // Leftover bits in a partial group must be zero for the encoding to
+        // be canonical.
+        if bytes[1 + length..].iter().any(|&byte| byte != 0) {
+            return Err(Pbkdf2Error::InvalidBase64);
+        }
+        decoded.extend_from_slice(&bytes[1..1 + length]);
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use alloc::{format, string::ToString};
+
+    #[cfg(feature = "alloc")]
+    fn derive(password: &[u8], salt: &[u8], iterations: u32, length: usize) -> String {
+        let mut output = vec![0; length];
+        pbkdf2_sha256(password, salt, iterations, &mut output).unwrap();
+        output.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_rfc7914() {
+        assert_eq!(
+            derive(b"passwd", b"salt", 1, 64),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+             49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+        assert_eq!(
+            derive(b"Password", b"NaCl", 80000, 64),
+            "4ddcd8f60b98be21830cee5ef22701f9641a4418d04c0414aeff08876b34ab56\
+             a1d425a1225833549adb841b51c9b3176a272bdebba1d078478f62b397f33c8d"
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_known_vectors() {
+        assert_eq!(
+            derive(b"password", b"salt", 1, 32),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+        assert_eq!(
+            derive(b"password", b"salt", 2, 32),
+            "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43"
+        );
+        assert_eq!(
+            derive(b"password", b"salt", 4096, 32),
+            "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"
+        );
+        assert_eq!(
+            derive(
+                b"passwordPASSWORDpassword",
+                b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+                4096,
+                40
+            ),
+            "348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1c635518c7dac47e9"
+        );
+        assert_eq!(
+            derive(b"pass\0word", b"sa\0lt", 4096, 16),
+            "89b69d0516f829893c696226650a8687"
+        );
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let mut output = [0u8; 32];
+        assert_eq!(
+            pbkdf2_sha256(b"password", b"salt", 0, &mut output),
+            Err(Pbkdf2Error::InvalidIterations)
+        );
+        assert_eq!(
+            pbkdf2_sha256(b"password", b"salt", 1, &mut []),
+            Err(Pbkdf2Error::InvalidOutputLength)
+        );
+
+        // Buffers that large can't be allocated, so check the limit directly.
+        // It is out of reach on 32-bit targets.
+        if let Ok(length) = usize::try_from(MAX_OUTPUT_LENGTH) {
+            assert_eq!(check_parameters(1, length), Ok(()));
+            assert_eq!(
+                check_parameters(1, length + 1),
+                Err(Pbkdf2Error::OutputTooLong)
+            );
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_phc_round_trip() {
+        let phc = "$pbkdf2-sha256$i=1000$c2FsdHNhbHRzYWx0c2FsdA$RilxBxnvGa3JIyaXwlUUKmvuPzxjHerJeqIuhiIvKNU";
+
+        let hash = Pbkdf2Hash::new(b"hunter2", b"saltsaltsaltsalt", 1000, 32).unwrap();
+        assert_eq!(hash.to_string(), phc);
+        assert_eq!(phc.parse::<Pbkdf2Hash>(), Ok(hash));
+
+        assert_eq!(verify_pbkdf2_sha256(b"hunter2", phc), Ok(true));
+        assert_eq!(verify_pbkdf2_sha256(b"hunter3", phc), Ok(false));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_phc_errors() {
+        assert_eq!(
+            "$pbkdf2-sha512$i=1000$c2FsdA$AAAA".parse::<Pbkdf2Hash>(),
+            Err(Pbkdf2Error::UnsupportedAlgorithm)
+        );
+        assert_eq!(
+            "$pbkdf2-sha256$1000$c2FsdA$AAAA".parse::<Pbkdf2Hash>(),
+            Err(Pbkdf2Error::InvalidFormat)
+        );
+        assert_eq!(
+            "$pbkdf2-sha256$i=1000$c2FsdA".parse::<Pbkdf2Hash>(),
+            Err(Pbkdf2Error::InvalidFormat)
+        );
+        assert_eq!(
+            "$pbkdf2-sha256$i=0$c2FsdA$AAAA".parse::<Pbkdf2Hash>(),
+            Err(Pbkdf2Error::InvalidIterations)
+        );
+        assert_eq!(
+            "$pbkdf2-sha256$i=01000$c2FsdA$AAAA".parse::<Pbkdf2Hash>(),
+            Err(Pbkdf2Error::InvalidFormat)
+        );
+        assert_eq!(
+            "$pbkdf2-sha256$i=00$c2FsdA$AAAA".parse::<Pbkdf2Hash>(),
+            Err(Pbkdf2Error::InvalidFormat)
+        );
+        assert_eq!(
+            "$pbkdf2-sha256$i=1000$c2F*dA$AAAA".parse::<Pbkdf2Hash>(),
+            Err(Pbkdf2Error::InvalidBase64)
+        );
+        assert_eq!(
+            "$pbkdf2-sha256$i=1000$c2FsdB$AAAA".parse::<Pbkdf2Hash>(),
+            Err(Pbkdf2Error::InvalidBase64)
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_base64() {
+        for length in 0..8 {
+            let bytes: Vec<u8> = (0..length).map(|i: u8| i.wrapping_mul(37) ^ 0xc8).collect();
+            assert_eq!(decode_base64(&encode_base64(&bytes)), Ok(bytes));
+        }
+        assert_eq!(encode_base64(b"salt"), "c2FsdA");
+    }
+}
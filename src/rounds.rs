@@ -0,0 +1,48 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+/// Defines the SHA-2 round functions for a word type. SHA-224/256 and the
+/// SHA-512 family share the same functions and differ only in word size and
+/// rotation amounts. The functions are `const` so the compile-time hasher can
+/// share them.
+macro_rules! round_functions {
+    (
+        $word:ty,
+        sig0: ($sig0_a:literal, $sig0_b:literal, $sig0_c:literal),
+        sig1: ($sig1_a:literal, $sig1_b:literal, $sig1_c:literal),
+        usig0: ($usig0_a:literal, $usig0_b:literal, $usig0_c:literal),
+        usig1: ($usig1_a:literal, $usig1_b:literal, $usig1_c:literal)
+    ) => {
+        #[inline]
+        const fn sig0(x: $word) -> $word {
+            x.rotate_right($sig0_a) ^ x.rotate_right($sig0_b) ^ x >> $sig0_c
+        }
+
+        #[inline]
+        const fn sig1(x: $word) -> $word {
+            x.rotate_right($sig1_a) ^ x.rotate_right($sig1_b) ^ x >> $sig1_c
+        }
+
+        #[inline]
+        const fn usig0(x: $word) -> $word {
+            x.rotate_right($usig0_a) ^ x.rotate_right($usig0_b) ^ x.rotate_right($usig0_c)
+        }
+
+        #[inline]
+        const fn usig1(x: $word) -> $word {
+            x.rotate_right($usig1_a) ^ x.rotate_right($usig1_b) ^ x.rotate_right($usig1_c)
+        }
+
+        #[inline]
+        const fn ch(x: $word, y: $word, z: $word) -> $word {
+            (x & y) ^ (!x & z)
+        }
+
+        #[inline]
+        const fn maj(x: $word, y: $word, z: $word) -> $word {
+            (x & y) ^ (x & z) ^ (y & z)
+        }
+    };
+}
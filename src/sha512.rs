@@ -0,0 +1,406 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! The 64-bit SHA-2 variants: SHA-384, SHA-512, SHA-512/224 and SHA-512/256.
+
+#![forbid(unsafe_code)]
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
+use crate::Digest;
+
+#[rustfmt::skip]
+const SQRT_CONST: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+// Fractional parts of the square roots of the 9th through 16th primes.
+#[rustfmt::skip]
+const SHA384_CONST: [u64; 8] = [
+    0xcbbb9d5dc1059ed8, 0x629a292a367cd507, 0x9159015a3070dd17, 0x152fecd8f70e5939,
+    0x67332667ffc00b31, 0x8eb44a8768581511, 0xdb0c2e0d64f98fa7, 0x47b5481dbefa4fa4,
+];
+
+// Produced by the SHA-512/t IV generation function in FIPS 180-4 section 5.3.6.
+#[rustfmt::skip]
+const SHA512_224_CONST: [u64; 8] = [
+    0x8c3d37c819544da2, 0x73e1996689dcd4d6, 0x1dfab7ae32ff9c82, 0x679dd514582f9fcf,
+    0x0f6d2b697bd44da8, 0x77e36f7304c48942, 0x3f9d85a86a1d36c8, 0x1112e6ad91d692a1,
+];
+
+#[rustfmt::skip]
+const SHA512_256_CONST: [u64; 8] = [
+    0x22312194fc2bf72c, 0x9f555fa3c84c64c2, 0x2393b86b6f53b151, 0x963877195940eabd,
+    0x96283ee2a88effe3, 0xbe5e1e2553863992, 0x2b0199fc2c85b8aa, 0x0eb72ddc81c52ca2,
+];
+
+#[rustfmt::skip]
+const CBRT_CONST: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+    0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+    0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+    0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+    0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+    0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+    0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+    0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+    0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+    0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+    0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+    0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+/// Convenience wrapper returning the digest as lowercase hex.
+#[cfg(feature = "alloc")]
+pub fn sha512(input: &str) -> String {
+    sha512_bytes(input.as_bytes()).to_hex()
+}
+
+pub fn sha512_bytes(input: &[u8]) -> Digest<64> {
+    let mut hasher = Sha512::new();
+    hasher.update(input);
+    hasher.finalize()
+}
+
+/// Convenience wrapper returning the digest as lowercase hex.
+#[cfg(feature = "alloc")]
+pub fn sha384(input: &str) -> String {
+    sha384_bytes(input.as_bytes()).to_hex()
+}
+
+pub fn sha384_bytes(input: &[u8]) -> Digest<48> {
+    let mut hasher = Sha384::new();
+    hasher.update(input);
+    hasher.finalize()
+}
+
+/// Convenience wrapper returning the digest as lowercase hex.
+#[cfg(feature = "alloc")]
+pub fn sha512_224(input: &str) -> String {
+    sha512_224_bytes(input.as_bytes()).to_hex()
+}
+
+pub fn sha512_224_bytes(input: &[u8]) -> Digest<28> {
+    let mut hasher = Sha512_224::new();
+    hasher.update(input);
+    hasher.finalize()
+}
+
+/// Convenience wrapper returning the digest as lowercase hex.
+#[cfg(feature = "alloc")]
+pub fn sha512_256(input: &str) -> String {
+    sha512_256_bytes(input.as_bytes()).to_hex()
+}
+
+pub fn sha512_256_bytes(input: &[u8]) -> Digest<32> {
+    let mut hasher = Sha512_256::new();
+    hasher.update(input);
+    hasher.finalize()
+}
+
+/// Streaming SHA-512 state. Works like [`crate::Sha256`] but over 128-byte
+/// blocks with a 128-bit message length.
+#[derive(Clone)]
+pub struct Sha512 {
+    hash: [u64; 8],
+    buffer: [u8; 128],
+    buffered: usize,
+    bit_length: u128,
+}
+
+impl Sha512 {
+    pub fn new() -> Self {
+        Self::with_initial(SQRT_CONST)
+    }
+
+    fn with_initial(initial: [u64; 8]) -> Self {
+        Self {
+            hash: initial,
+            buffer: [0; 128],
+            buffered: 0,
+            bit_length: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        self.bit_length = self
+            .bit_length
+            .wrapping_add((bytes.len() as u128).wrapping_mul(8));
+
+        if self.buffered > 0 {
+            let take = (128 - self.buffered).min(bytes.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&bytes[..take]);
+            self.buffered += take;
+            bytes = &bytes[take..];
+
+            if self.buffered < 128 {
+                return;
+            }
+            let block = self.buffer;
+            self.process_block(&block);
+            self.buffered = 0;
+        }
+
+        let (blocks, remainder) = bytes.as_chunks::<128>();
+        for block in blocks {
+            self.process_block(block);
+        }
+
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffered = remainder.len();
+    }
+
+    pub fn finalize(self) -> Digest<64> {
+        get_digest(&self.finish())
+    }
+
+    /// Pads the message and returns the final hash words.
+    fn finish(mut self) -> [u64; 8] {
+        // A single 0x80 byte, then zeros and the big-endian bit length in the
+        // last 16 bytes, spilling into a second block when fewer than 17 bytes
+        // are free. `buffered` is always below 128 between updates.
+        self.buffer[self.buffered..].fill(0);
+        self.buffer[self.buffered] = 0x80;
+        if self.buffered >= 112 {
+            let block = self.buffer;
+            self.process_block(&block);
+            self.buffer = [0; 128];
+        }
+        self.buffer[112..].copy_from_slice(&self.bit_length.to_be_bytes());
+        let block = self.buffer;
+        self.process_block(&block);
+
+        self.hash
+    }
+
+    fn process_block(&mut self, block: &[u8; 128]) {
+        let schedule = create_message_schedule(block);
+        self.hash = do_compression(self.hash, &schedule);
+    }
+}
+
+impl Default for Sha512 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Declares a SHA-512 based hasher with its own initial hash value and a
+/// truncated output.
+macro_rules! truncated_sha512 {
+    ($(#[$attr:meta])* $name:ident, $initial:ident, $size:literal) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            inner: Sha512,
+        }
+
+        impl $name {
+            pub fn new() -> Self {
+                Self {
+                    inner: Sha512::with_initial($initial),
+                }
+            }
+
+            pub fn update(&mut self, bytes: &[u8]) {
+                self.inner.update(bytes);
+            }
+
+            pub fn finalize(self) -> Digest<$size> {
+                get_digest(&self.inner.finish())
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    };
+}
+
+truncated_sha512!(
+    /// Streaming SHA-384 state.
+    Sha384,
+    SHA384_CONST,
+    48
+);
+
+truncated_sha512!(
+    /// Streaming SHA-512/224 state.
+    Sha512_224,
+    SHA512_224_CONST,
+    28
+);
+
+truncated_sha512!(
+    /// Streaming SHA-512/256 state.
+    Sha512_256,
+    SHA512_256_CONST,
+    32
+);
+
+fn create_message_schedule(block: &[u8; 128]) -> [u64; 80] {
+    let mut schedule: [u64; 80] = [0; 80];
+
+    let (words, _) = block.as_chunks::<8>();
+    for (scheduled, word) in schedule.iter_mut().zip(words) {
+        *scheduled = u64::from_be_bytes(*word);
+    }
+
+    for i in 16..80 {
+        let calculated: u64 = sig1(schedule[i - 2])
+            .wrapping_add(schedule[i - 7])
+            .wrapping_add(sig0(schedule[i - 15]))
+            .wrapping_add(schedule[i - 16]);
+        schedule[i] = calculated;
+    }
+
+    schedule
+}
+
+fn do_compression(initial: [u64; 8], schedule: &[u64; 80]) -> [u64; 8] {
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = initial;
+
+    for (word, constant) in schedule.iter().zip(CBRT_CONST) {
+        let temp1 = usig1(e)
+            .wrapping_add(ch(e, f, g))
+            .wrapping_add(h)
+            .wrapping_add(constant)
+            .wrapping_add(*word);
+        let temp2 = usig0(a).wrapping_add(maj(a, b, c));
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    [
+        initial[0].wrapping_add(a),
+        initial[1].wrapping_add(b),
+        initial[2].wrapping_add(c),
+        initial[3].wrapping_add(d),
+        initial[4].wrapping_add(e),
+        initial[5].wrapping_add(f),
+        initial[6].wrapping_add(g),
+        initial[7].wrapping_add(h),
+    ]
+}
+
+fn get_digest<const N: usize>(compressed: &[u64; 8]) -> Digest<N> {
+    let mut bytes: [u8; N] = [0; N];
+    for (chunk, word) in bytes.chunks_mut(8).zip(compressed) {
+        chunk.copy_from_slice(&word.to_be_bytes()[..chunk.len()]);
+    }
+
+    Digest(bytes)
+}
+
+round_functions!(
+    u64,
+    sig0: (1, 8, 7),
+    sig1: (19, 61, 6),
+    usig0: (28, 34, 39),
+    usig1: (14, 18, 41)
+);
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    const TWO_BLOCK: &str = "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+
+    #[test]
+    fn test_fips_examples() {
+        assert_eq!(
+            sha512("abc"),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+        assert_eq!(
+            sha512(TWO_BLOCK),
+            "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"
+        );
+        assert_eq!(
+            sha384("abc"),
+            "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"
+        );
+        assert_eq!(
+            sha384(TWO_BLOCK),
+            "09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039"
+        );
+        assert_eq!(
+            sha512_224("abc"),
+            "4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa"
+        );
+        assert_eq!(
+            sha512_224(TWO_BLOCK),
+            "23fec5bb94d60b23308192640b0c453335d664734fe40e7268674af9"
+        );
+        assert_eq!(
+            sha512_256("abc"),
+            "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23"
+        );
+        assert_eq!(
+            sha512_256(TWO_BLOCK),
+            "3928e184fb8690f840da3988121d31be65cb9d3ef83ee6146feac861e19b563a"
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(
+            sha512(""),
+            "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"
+        );
+        assert_eq!(
+            sha384(""),
+            "38b060a751ac96384cd9327eb1b1e36a21fdb71114be07434c0cc7bf63f6e1da274edebfe76f65fbd51ad2f14898b95b"
+        );
+        assert_eq!(
+            sha512_224(""),
+            "6ed0dd02806fa89e25de060c19d3ac86cabb87d6a0ddd05c333b84f4"
+        );
+        assert_eq!(
+            sha512_256(""),
+            "c672b8d1ef56ed28ab87c3622c5114069bdd3ad7b8f9737498d0c01ecef0967a"
+        );
+    }
+
+    #[test]
+    fn test_streaming_million_a() {
+        let chunk = [b'a'; 1000];
+        let mut hasher = Sha512::new();
+        for _ in 0..1000 {
+            hasher.update(&chunk);
+        }
+        assert_eq!(
+            hasher.finalize().to_hex(),
+            "e718483d0ce769644e2e42c7bc15b4638e1f98b13b2044285632a803afa973ebde0ff244877ea60a4cb0432ce577c31beb009c5c2c49aa2e4eadb217ad8cc09b"
+        );
+
+        let input = TWO_BLOCK.repeat(3);
+        for split in [0, 1, 111, 112, 127, 128, 129, input.len()] {
+            let mut hasher = Sha384::new();
+            hasher.update(&input.as_bytes()[..split]);
+            hasher.update(&input.as_bytes()[split..]);
+            assert_eq!(hasher.finalize(), sha384_bytes(input.as_bytes()));
+        }
+    }
+}
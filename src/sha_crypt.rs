@@ -0,0 +1,316 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! The SHA-256 based Unix crypt scheme (`$5$`) as described in Ulrich
+//! Drepper's "Unix crypt using SHA-256 and SHA-512".
+
+#![forbid(unsafe_code)]
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+use crate::digest::constant_time_eq;
+use crate::{Digest, Sha256};
+
+const PREFIX: &str = "$5$";
+const ROUNDS_PREFIX: &str = "rounds=";
+const SALT_MAX_LENGTH: usize = 16;
+const ROUNDS_DEFAULT: u32 = 5000;
+const ROUNDS_MIN: u32 = 1000;
+const ROUNDS_MAX: u32 = 999_999_999;
+
+const CRYPT_ALPHABET: &[u8; 64] =
+    b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// The order in which digest bytes are fed to the base64 encoder, three at a
+// time. The final group only holds two bytes.
+const ENCODE_ORDER: [[usize; 3]; 10] = [
+    [0, 10, 20],
+    [21, 1, 11],
+    [12, 22, 2],
+    [3, 13, 23],
+    [24, 4, 14],
+    [15, 25, 5],
+    [6, 16, 26],
+    [27, 7, 17],
+    [18, 28, 8],
+    [9, 19, 29],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaCryptError {
+    /// The setting or hash did not start with `$5$`.
+    UnsupportedScheme,
+    /// Cutting the salt to 16 bytes would split a character, so the hash
+    /// couldn't be written as a string.
+    InvalidSalt,
+}
+
+impl fmt::Display for ShaCryptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedScheme => f.write_str("expected a $5$ SHA-256-crypt setting"),
+            Self::InvalidSalt => f.write_str("salt truncated in the middle of a character"),
+        }
+    }
+}
+
+impl core::error::Error for ShaCryptError {}
+
+/// Hashes `password` with a crypt(3) style setting such as `$5$salt` or
+/// `$5$rounds=10000$salt`. Anything after the salt, like an existing hash, is
+/// ignored, so a stored hash can be passed back in as its own setting.
+///
+/// Salts are truncated to 16 bytes and the round count is clamped to
+/// 1000..=999999999, matching the reference implementation.
+pub fn sha256_crypt(password: &[u8], setting: &str) -> Result<String, ShaCryptError> {
+    let rest = setting
+        .strip_prefix(PREFIX)
+        .ok_or(ShaCryptError::UnsupportedScheme)?;
+    let (rounds, rest) = parse_rounds(rest);
+
+    let salt_end = rest.find('$').unwrap_or(rest.len());
+    let salt = truncate_salt(&rest[..salt_end])?;
+
+    let hash = hash_password(password, salt.as_bytes(), rounds.unwrap_or(ROUNDS_DEFAULT));
+
+    let mut output = String::from(PREFIX);
+    if let Some(rounds) = rounds {
+        output.push_str(&format!("{}{}$", ROUNDS_PREFIX, rounds));
+    }
+    output.push_str(salt);
+    output.push('$');
+    output.push_str(&encode_hash(&hash));
+
+    Ok(output)
+}
+
+/// Checks `password` against a stored `$5$` hash, comparing in constant time.
+pub fn verify_sha256_crypt(password: &[u8], hash: &str) -> Result<bool, ShaCryptError> {
+    let computed = sha256_crypt(password, hash)?;
+    Ok(constant_time_eq(computed.as_bytes(), hash.as_bytes()))
+}
+
+/// Splits off a leading `rounds=<n>$`, returning the clamped round count. As
+/// in the reference implementation, a malformed rounds field is treated as
+/// part of the salt.
+fn parse_rounds(setting: &str) -> (Option<u32>, &str) {
+    let Some(rest) = setting.strip_prefix(ROUNDS_PREFIX) else {
+        return (None, setting);
+    };
+    let digits_end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let Some(after) = rest[digits_end..].strip_prefix('$') else {
+        return (None, setting);
+    };
+
+    let requested = rest[..digits_end].bytes().fold(0u64, |total, digit| {
+        total
+            .saturating_mul(10)
+            .saturating_add(u64::from(digit - b'0'))
+    });
+    let rounds = requested.clamp(u64::from(ROUNDS_MIN), u64::from(ROUNDS_MAX)) as u32;
+
+    (Some(rounds), after)
+}
+
+/// Cuts the salt to its first 16 bytes, as the reference implementation does
+/// regardless of encoding.
+fn truncate_salt(salt: &str) -> Result<&str, ShaCryptError> {
+    salt.get(..salt.len().min(SALT_MAX_LENGTH))
+        .ok_or(ShaCryptError::InvalidSalt)
+}
+
+fn digest_of(parts: &[&[u8]]) -> Digest {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize()
+}
+
+/// Repeats `digest` until it covers `length` bytes.
+fn stretch(digest: &Digest, length: usize) -> Vec<u8> {
+    digest
+        .as_bytes()
+        .iter()
+        .copied()
+        .cycle()
+        .take(length)
+        .collect()
+}
+
+fn hash_password(password: &[u8], salt: &[u8], rounds: u32) -> Digest {
+    let alternate = digest_of(&[password, salt, password]);
+
+    let mut hasher = Sha256::new();
+    hasher.update(password);
+    hasher.update(salt);
+    for chunk in stretch(&alternate, password.len()).chunks(32) {
+        hasher.update(chunk);
+    }
+    let mut length = password.len();
+    while length > 0 {
+        if length & 1 != 0 {
+            hasher.update(alternate.as_bytes());
+        } else {
+            hasher.update(password);
+        }
+        length >>= 1;
+    }
+    let mut current = hasher.finalize();
+
+    let mut hasher = Sha256::new();
+    for _ in 0..password.len() {
+        hasher.update(password);
+    }
+    let p_bytes = stretch(&hasher.finalize(), password.len());
+
+    let mut hasher = Sha256::new();
+    for _ in 0..16 + usize::from(current.as_bytes()[0]) {
+        hasher.update(salt);
+    }
+    let s_bytes = stretch(&hasher.finalize(), salt.len());
+
+    for round in 0..rounds {
+        let mut hasher = Sha256::new();
+        if round % 2 == 1 {
+            hasher.update(&p_bytes);
+        } else {
+            hasher.update(current.as_bytes());
+        }
+        if round % 3 != 0 {
+            hasher.update(&s_bytes);
+        }
+        if round % 7 != 0 {
+            hasher.update(&p_bytes);
+        }
+        if round % 2 == 1 {
+            hasher.update(current.as_bytes());
+        } else {
+            hasher.update(&p_bytes);
+        }
+        current = hasher.finalize();
+    }
+
+    current
+}
+
+/// Encodes the digest with crypt's base64 variant, which uses its own
+/// alphabet and emits the low six bits of each 24-bit group first.
+fn encode_hash(hash: &Digest) -> String {
+    let bytes = hash.as_bytes();
+    let mut encoded = String::with_capacity(43);
+
+    let mut push_group = |high: u8, middle: u8, low: u8, characters: usize| {
+        let mut bits = u32::from(high) << 16 | u32::from(middle) << 8 | u32::from(low);
+        for _ in 0..characters {
+            encoded.push(CRYPT_ALPHABET[(bits & 0x3f) as usize] as char);
+            bits >>= 6;
+        }
+    };
+
+    for [high, middle, low] in ENCODE_ORDER {
+        push_group(bytes[high], bytes[middle], bytes[low], 4);
+    }
+    push_group(0, bytes[31], bytes[30], 3);
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_vectors() {
+        let vectors = [
+            (
+                "$5$saltstring",
+                "Hello world!",
+                "$5$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5",
+            ),
+            (
+                "$5$rounds=10000$saltstringsaltstring",
+                "Hello world!",
+                "$5$rounds=10000$saltstringsaltst$3xv.VbSHBb41AL9AvLeujZkZRBAwqFMz2.opqey6IcA",
+            ),
+            (
+                "$5$rounds=5000$toolongsaltstring",
+                "This is just a test",
+                "$5$rounds=5000$toolongsaltstrin$Un/5jzAHMgOGZ5.mWJpuVolil07guHPvOW8mGRcvxa5",
+            ),
+            (
+                "$5$rounds=1400$anotherlongsaltstring",
+                "a very much longer text to encrypt.  This one even stretches over morethan one line.",
+                "$5$rounds=1400$anotherlongsalts$Rx.j8H.h8HjEDGomFU8bDkXm3XIUnzyxf12oP84Bnq1",
+            ),
+            (
+                "$5$rounds=77777$short",
+                "we have a short salt string but not a short password",
+                "$5$rounds=77777$short$JiO1O3ZpDAxGJeaDIuqCoEFysAe1mZNJRs3pw0KQRd/",
+            ),
+            (
+                "$5$rounds=123456$asaltof16chars..",
+                "a short string",
+                "$5$rounds=123456$asaltof16chars..$gP3VQ/6X7UUEW3HkBn2w1/Ptq2jxPyzV/cZKmF/wJvD",
+            ),
+            (
+                "$5$rounds=10$roundstoolow",
+                "the minimum number is still observed",
+                "$5$rounds=1000$roundstoolow$yfvwcWrQ8l/K0DAWyuPMDNHpIVlTQebY9l/gL972bIC",
+            ),
+        ];
+
+        for (setting, password, expected) in vectors {
+            assert_eq!(
+                sha256_crypt(password.as_bytes(), setting).as_deref(),
+                Ok(expected)
+            );
+        }
+    }
+
+    /// Salts are cut by bytes, not characters. Checked against a separate
+    /// implementation of Drepper's algorithm, as libxcrypt refuses salts
+    /// outside the crypt alphabet.
+    #[test]
+    fn test_multibyte_salt() {
+        assert_eq!(
+            sha256_crypt("pässwörd".as_bytes(), "$5$rounds=10000$ééééééééé").as_deref(),
+            Ok("$5$rounds=10000$éééééééé$atTLIr9yoM.kqj2Ck.jdLB8XZFfqAyg37DsTI6itcT8")
+        );
+        assert_eq!(
+            sha256_crypt(b"Hello world!", "$5$sälzsälzsälzsälz").as_deref(),
+            Ok("$5$sälzsälzsälzs$9Po94g0T4xOo6Hn4AN0KtIgP7OwUaiGPGAnWYQ4kTY6")
+        );
+        assert_eq!(
+            sha256_crypt(b"Hello world!", "$5$saltsaltsaltsalé"),
+            Err(ShaCryptError::InvalidSalt)
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let stored = "$5$rounds=10000$saltstringsaltst$3xv.VbSHBb41AL9AvLeujZkZRBAwqFMz2.opqey6IcA";
+        assert_eq!(verify_sha256_crypt(b"Hello world!", stored), Ok(true));
+        assert_eq!(verify_sha256_crypt(b"Hello world?", stored), Ok(false));
+        assert_eq!(
+            verify_sha256_crypt(b"Hello world!", "$6$saltstring$abc"),
+            Err(ShaCryptError::UnsupportedScheme)
+        );
+    }
+
+    #[test]
+    fn test_rounds_parsing() {
+        assert_eq!(parse_rounds("rounds=2000$salt"), (Some(2000), "salt"));
+        assert_eq!(
+            parse_rounds("rounds=99999999999999999999$salt"),
+            (Some(ROUNDS_MAX), "salt")
+        );
+        assert_eq!(parse_rounds("rounds=12x$salt"), (None, "rounds=12x$salt"));
+        assert_eq!(parse_rounds("salt$hash"), (None, "salt$hash"));
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! SHA-256 compression using the x86_64 SHA extensions. Each
+//! `sha256rnds2` performs two rounds on the state split into ABEF and CDGH
+//! halves, and `sha256msg1`/`sha256msg2` extend the message schedule four
+//! words at a time.
+
+#![allow(unsafe_code)]
+
+use core::arch::x86_64::*;
+
+use crate::CBRT_CONST;
+
+#[cfg(feature = "std")]
+pub(crate) fn is_supported() -> bool {
+    std::is_x86_feature_detected!("sha")
+        && std::is_x86_feature_detected!("sse2")
+        && std::is_x86_feature_detected!("ssse3")
+        && std::is_x86_feature_detected!("sse4.1")
+}
+
+/// Without `std` there is no runtime detection, so the extensions are only
+/// used when the build targets them.
+#[cfg(not(feature = "std"))]
+pub(crate) fn is_supported() -> bool {
+    cfg!(all(
+        target_feature = "sha",
+        target_feature = "sse2",
+        target_feature = "ssse3",
+        target_feature = "sse4.1"
+    ))
+}
+
+/// Four rounds using the message words in `$words` and constants
+/// `CBRT_CONST[$i * 4..$i * 4 + 4]`.
+macro_rules! rounds4 {
+    ($abef:ident, $cdgh:ident, $words:expr, $i:expr) => {{
+        let constants = _mm_loadu_si128(CBRT_CONST.as_ptr().add($i * 4) as *const __m128i);
+        let scheduled = _mm_add_epi32($words, constants);
+        $cdgh = _mm_sha256rnds2_epu32($cdgh, $abef, scheduled);
+        let scheduled = _mm_shuffle_epi32(scheduled, 0x0e);
+        $abef = _mm_sha256rnds2_epu32($abef, $cdgh, scheduled);
+    }};
+}
+
+/// Computes the next four schedule words into `$next` from the previous
+/// sixteen, then runs four rounds with them.
+macro_rules! schedule_rounds4 {
+    ($abef:ident, $cdgh:ident, $w0:ident, $w1:ident, $w2:ident, $w3:ident, $next:ident, $i:expr) => {{
+        let partial = _mm_sha256msg1_epu32($w0, $w1);
+        let partial = _mm_add_epi32(partial, _mm_alignr_epi8($w3, $w2, 4));
+        $next = _mm_sha256msg2_epu32(partial, $w3);
+        rounds4!($abef, $cdgh, $next, $i);
+    }};
+}
+
+/// Compresses `blocks` into `hash` if the CPU has the SHA extensions, returning
+/// whether it did.
+pub(crate) fn try_compress(hash: &mut [u32; 8], blocks: &[u8]) -> bool {
+    if !is_supported() {
+        return false;
+    }
+    // SAFETY: the CPU features `compress` needs were detected.
+    unsafe { compress(hash, blocks) };
+    true
+}
+
+/// # Safety
+///
+/// The CPU must support the `sha`, `sse2`, `ssse3` and `sse4.1` features, see
+/// [`is_supported`].
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub(crate) unsafe fn compress(hash: &mut [u32; 8], blocks: &[u8]) {
+    let byte_swap = _mm_set_epi64x(
+        0x0c0d_0e0f_0809_0a0bu64 as i64,
+        0x0405_0607_0001_0203u64 as i64,
+    );
+
+    let hash_ptr = hash.as_ptr() as *const __m128i;
+    let dcba = _mm_loadu_si128(hash_ptr);
+    let hgfe = _mm_loadu_si128(hash_ptr.add(1));
+
+    let cdab = _mm_shuffle_epi32(dcba, 0xb1);
+    let efgh = _mm_shuffle_epi32(hgfe, 0x1b);
+    let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+    let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xf0);
+
+    for block in blocks.chunks_exact(64) {
+        let abef_initial = abef;
+        let cdgh_initial = cdgh;
+
+        let block_ptr = block.as_ptr() as *const __m128i;
+        let mut w0 = _mm_shuffle_epi8(_mm_loadu_si128(block_ptr), byte_swap);
+        let mut w1 = _mm_shuffle_epi8(_mm_loadu_si128(block_ptr.add(1)), byte_swap);
+        let mut w2 = _mm_shuffle_epi8(_mm_loadu_si128(block_ptr.add(2)), byte_swap);
+        let mut w3 = _mm_shuffle_epi8(_mm_loadu_si128(block_ptr.add(3)), byte_swap);
+        let mut w4;
+
+        rounds4!(abef, cdgh, w0, 0);
+        rounds4!(abef, cdgh, w1, 1);
+        rounds4!(abef, cdgh, w2, 2);
+        rounds4!(abef, cdgh, w3, 3);
+        schedule_rounds4!(abef, cdgh, w0, w1, w2, w3, w4, 4);
+        schedule_rounds4!(abef, cdgh, w1, w2, w3, w4, w0, 5);
+        schedule_rounds4!(abef, cdgh, w2, w3, w4, w0, w1, 6);
+        schedule_rounds4!(abef, cdgh, w3, w4, w0, w1, w2, 7);
+        schedule_rounds4!(abef, cdgh, w4, w0, w1, w2, w3, 8);
+        schedule_rounds4!(abef, cdgh, w0, w1, w2, w3, w4, 9);
+        schedule_rounds4!(abef, cdgh, w1, w2, w3, w4, w0, 10);
+        schedule_rounds4!(abef, cdgh, w2, w3, w4, w0, w1, 11);
+        schedule_rounds4!(abef, cdgh, w3, w4, w0, w1, w2, 12);
+        schedule_rounds4!(abef, cdgh, w4, w0, w1, w2, w3, 13);
+        schedule_rounds4!(abef, cdgh, w0, w1, w2, w3, w4, 14);
+        schedule_rounds4!(abef, cdgh, w1, w2, w3, w4, w0, 15);
+
+        abef = _mm_add_epi32(abef, abef_initial);
+        cdgh = _mm_add_epi32(cdgh, cdgh_initial);
+    }
+
+    let feba = _mm_shuffle_epi32(abef, 0x1b);
+    let dchg = _mm_shuffle_epi32(cdgh, 0xb1);
+    let dcba = _mm_blend_epi16(feba, dchg, 0xf0);
+    let hgef = _mm_alignr_epi8(dchg, feba, 8);
+
+    let hash_ptr = hash.as_mut_ptr() as *mut __m128i;
+    _mm_storeu_si128(hash_ptr, dcba);
+    _mm_storeu_si128(hash_ptr.add(1), hgef);
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::compress_portable;
+    use crate::test_util::TestRng;
+
+    #[test]
+    fn test_matches_portable() {
+        if !is_supported() {
+            eprintln!("skipping: CPU lacks SHA extensions");
+            return;
+        }
+
+        let mut rng = TestRng(0x9e3779b97f4a7c15);
+        for _ in 0..200 {
+            let mut hash = [0u32; 8];
+            for word in &mut hash {
+                *word = rng.next() as u32;
+            }
+            let mut blocks = vec![0u8; 64 * (rng.next() % 8) as usize];
+            rng.fill(&mut blocks);
+
+            let mut expected = hash;
+            compress_portable(&mut expected, &blocks);
+            let mut actual = hash;
+            // SAFETY: support was checked above.
+            unsafe { compress(&mut actual, &blocks) };
+
+            assert_eq!(actual, expected);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Exported [`Sha256`](crate::Sha256) state, so hashing can be checkpointed
+//! and resumed in another process.
+//!
+//! The binary encoding is 106 bytes:
+//!
+//! | Offset | Length | Contents                                      |
+//! |--------|--------|-----------------------------------------------|
+//! | 0      | 1      | format version, currently 1                   |
+//! | 1      | 32     | the eight hash words, big-endian              |
+//! | 33     | 8      | message length so far in bits, big-endian     |
+//! | 41     | 1      | number of buffered bytes, below 64            |
+//! | 42     | 64     | buffered bytes, zero padded                   |
+//!
+//! The hex encoding is the binary encoding as lowercase hex.
+
+#![forbid(unsafe_code)]
+
+#[cfg(feature = "alloc")]
+use alloc::{format, string::String};
+use core::fmt;
+use core::str::FromStr;
+
+use crate::digest::decode_hex;
+
+const VERSION: u8 = 1;
+
+/// Hash words, bit length and buffered tail of a [`Sha256`](crate::Sha256)
+/// between updates. Created by [`Sha256::state`](crate::Sha256::state) and
+/// resumed with [`Sha256::from_state`](crate::Sha256::from_state).
+#[derive(Clone, PartialEq, Eq)]
+pub struct Sha256State {
+    pub(crate) hash: [u32; 8],
+    pub(crate) bit_length: u64,
+    pub(crate) buffer: [u8; 64],
+    pub(crate) buffered: usize,
+}
+
+impl Sha256State {
+    /// Length of the binary encoding in bytes.
+    pub const ENCODED_LENGTH: usize = 106;
+
+    /// The intermediate hash words. After a whole number of blocks this is the
+    /// midstate other SHA-256 implementations can resume from.
+    pub fn hash_words(&self) -> [u32; 8] {
+        self.hash
+    }
+
+    /// Number of message bits hashed so far, including buffered bytes.
+    pub fn bit_length(&self) -> u64 {
+        self.bit_length
+    }
+
+    /// Bytes waiting for the current block to fill.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer[..self.buffered]
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LENGTH] {
+        let mut bytes = [0u8; Self::ENCODED_LENGTH];
+        bytes[0] = VERSION;
+        for (chunk, word) in bytes[1..33].chunks_exact_mut(4).zip(self.hash) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        bytes[33..41].copy_from_slice(&self.bit_length.to_be_bytes());
+        bytes[41] = self.buffered as u8;
+        bytes[42..42 + self.buffered].copy_from_slice(self.buffered());
+        bytes
+    }
+
+    /// Decodes the binary encoding. Encodings that no [`Sha256`](crate::Sha256)
+    /// could have produced are rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseStateError> {
+        let Ok(bytes) = <&[u8; Self::ENCODED_LENGTH]>::try_from(bytes) else {
+            return Err(ParseStateError::InvalidLength(bytes.len()));
+        };
+        if bytes[0] != VERSION {
+            return Err(ParseStateError::UnsupportedVersion(bytes[0]));
+        }
+
+        let mut hash = [0u32; 8];
+        let (words, _) = bytes[1..33].as_chunks::<4>();
+        for (word, chunk) in hash.iter_mut().zip(words) {
+            *word = u32::from_be_bytes(*chunk);
+        }
+
+        let mut length = [0u8; 8];
+        length.copy_from_slice(&bytes[33..41]);
+        let bit_length = u64::from_be_bytes(length);
+
+        let buffered = usize::from(bytes[41]);
+        let mut buffer = [0u8; 64];
+        buffer.copy_from_slice(&bytes[42..]);
+
+        // The buffer holds exactly the bytes past the last full block, and
+        // anything after them must be zero so each state has one encoding.
+        if bit_length % 8 != 0
+            || (bit_length / 8 % 64) as usize != buffered
+            || buffer[buffered..].iter().any(|&byte| byte != 0)
+        {
+            return Err(ParseStateError::Inconsistent);
+        }
+
+        Ok(Self {
+            hash,
+            bit_length,
+            buffer,
+            buffered,
+        })
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn to_hex(&self) -> String {
+        format!("{:x}", self)
+    }
+}
+
+impl fmt::LowerHex for Sha256State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.to_bytes() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Sha256State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl fmt::Debug for Sha256State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sha256State")
+            .field("hash", &self.hash)
+            .field("bit_length", &self.bit_length)
+            .field("buffered", &self.buffered())
+            .finish()
+    }
+}
+
+impl FromStr for Sha256State {
+    type Err = ParseStateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.as_bytes();
+        if hex.len() != Self::ENCODED_LENGTH * 2 {
+            return Err(ParseStateError::InvalidLength(hex.len()));
+        }
+
+        let mut bytes = [0u8; Self::ENCODED_LENGTH];
+        decode_hex(hex, &mut bytes).map_err(ParseStateError::InvalidCharacter)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStateError {
+    /// The input was not the length of an encoded state.
+    InvalidLength(usize),
+    /// A character at the given byte offset was not a hex digit.
+    InvalidCharacter(usize),
+    /// The state was encoded with a format version this build can't read.
+    UnsupportedVersion(u8),
+    /// The bit length, buffered byte count and buffer contents disagree.
+    Inconsistent,
+}
+
+impl fmt::Display for ParseStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength(length) => write!(f, "invalid state length of {}", length),
+            Self::InvalidCharacter(index) => {
+                write!(f, "invalid hex character at position {}", index)
+            }
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported state version {}", version)
+            }
+            Self::Inconsistent => f.write_str("state fields are inconsistent"),
+        }
+    }
+}
+
+impl core::error::Error for ParseStateError {}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    use super::*;
+    use crate::{sha256_bytes, Sha256};
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_resume() {
+        let input = "The quick brown fox jumps over the lazy dog. ".repeat(10);
+        let expected = sha256_bytes(input.as_bytes());
+
+        for split in [0, 1, 55, 63, 64, 65, 128, input.len()] {
+            let mut hasher = Sha256::new();
+            hasher.update(&input.as_bytes()[..split]);
+            let bytes = hasher.state().to_bytes();
+            let hex = hasher.state().to_hex();
+
+            let mut resumed = Sha256::from_state(&Sha256State::from_bytes(&bytes).unwrap());
+            resumed.update(&input.as_bytes()[split..]);
+            assert_eq!(resumed.finalize(), expected);
+
+            let mut resumed = Sha256::from_state(&hex.parse().unwrap());
+            resumed.update(&input.as_bytes()[split..]);
+            assert_eq!(resumed.finalize(), expected);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_encoding_is_stable() {
+        let mut hasher = Sha256::new();
+        hasher.update(b"abc");
+        assert_eq!(
+            hasher.state().to_hex(),
+            "016a09e667bb67ae853c6ef372a54ff53a510e527f9b05688c1f83d9ab5be0cd19\
+             0000000000000018\
+             03616263\
+             0000000000000000000000000000000000000000000000000000000000000\
+             0000000000000000000000000000000000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_midstate() {
+        let header = [0x5a; 80];
+        let mut hasher = Sha256::new();
+        hasher.update(&header[..64]);
+        let state = hasher.state();
+        assert_eq!(state.bit_length(), 512);
+        assert!(state.buffered().is_empty());
+
+        let mut resumed = Sha256::from_state(&state);
+        resumed.update(&header[64..]);
+        assert_eq!(resumed.finalize(), sha256_bytes(&header));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_rejects_invalid_states() {
+        let mut hasher = Sha256::new();
+        hasher.update(b"abc");
+        let bytes = hasher.state().to_bytes();
+
+        assert_eq!(
+            Sha256State::from_bytes(&bytes[..105]),
+            Err(ParseStateError::InvalidLength(105))
+        );
+
+        let mut wrong_version = bytes;
+        wrong_version[0] = 2;
+        assert_eq!(
+            Sha256State::from_bytes(&wrong_version),
+            Err(ParseStateError::UnsupportedVersion(2))
+        );
+
+        let mut wrong_count = bytes;
+        wrong_count[41] = 4;
+        assert_eq!(
+            Sha256State::from_bytes(&wrong_count),
+            Err(ParseStateError::Inconsistent)
+        );
+
+        let mut stale_buffer = bytes;
+        stale_buffer[105] = 1;
+        assert_eq!(
+            Sha256State::from_bytes(&stale_buffer),
+            Err(ParseStateError::Inconsistent)
+        );
+
+        let mut hex = hasher.state().to_hex();
+        hex.replace_range(2..3, "g");
+        assert_eq!(
+            hex.parse::<Sha256State>(),
+            Err(ParseStateError::InvalidCharacter(2))
+        );
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright (c) 2022 Ethan Lerner
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! End-to-end tests of the `sha256` binary: output format, check mode
+//! reports and exit codes.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+const EMPTY: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// A scratch directory for one test, removed on drop.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("sha256-cli-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir(&path).unwrap();
+        Self(path)
+    }
+
+    fn write(&self, name: impl AsRef<Path>, contents: impl AsRef<[u8]>) {
+        fs::write(self.0.join(name), contents).unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Runs the binary in `dir` with `stdin` as standard input.
+fn run(dir: &TempDir, args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sha256"))
+        .args(args)
+        .current_dir(&dir.0)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn test_hash_files() {
+    let dir = TempDir::new("hash");
+    dir.write("abc.txt", "abc");
+    dir.write("empty", "");
+
+    let output = run(&dir, &["abc.txt", "empty"], b"");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        format!("{}  abc.txt\n{}  empty\n", ABC, EMPTY)
+    );
+
+    let output = run(&dir, &["--tag", "abc.txt"], b"");
+    assert_eq!(stdout(&output), format!("SHA256 (abc.txt) = {}\n", ABC));
+
+    let output = run(&dir, &["-b", "-"], b"abc");
+    assert_eq!(stdout(&output), format!("{} *-\n", ABC));
+}
+
+#[test]
+fn test_missing_file() {
+    let dir = TempDir::new("missing");
+    dir.write("abc.txt", "abc");
+
+    let output = run(&dir, &["missing", "abc.txt"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), format!("{}  abc.txt\n", ABC));
+    assert_eq!(
+        stderr(&output),
+        "sha256: missing: No such file or directory\n"
+    );
+}
+
+#[test]
+fn test_invalid_options() {
+    let dir = TempDir::new("options");
+    let output = run(&dir, &["--bogus"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("sha256: unrecognized option '--bogus'\n"));
+
+    let output = run(&dir, &["--status"], b"");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_check() {
+    let dir = TempDir::new("check");
+    dir.write("abc.txt", "abc");
+    dir.write("changed.txt", "abd");
+    dir.write(
+        "good.sha256",
+        format!("{}  abc.txt\nSHA256 (abc.txt) = {}\n", ABC, ABC),
+    );
+    dir.write(
+        "mixed.sha256",
+        format!("{}  abc.txt\n{}  changed.txt\n", ABC, ABC),
+    );
+
+    let output = run(&dir, &["-c", "good.sha256"], b"");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "abc.txt: OK\nabc.txt: OK\n");
+    assert_eq!(stderr(&output), "");
+
+    let output = run(&dir, &["-c", "mixed.sha256"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "abc.txt: OK\nchanged.txt: FAILED\n");
+    assert_eq!(
+        stderr(&output),
+        "sha256: WARNING: 1 computed checksum did NOT match\n"
+    );
+
+    let output = run(&dir, &["-c", "--quiet", "mixed.sha256"], b"");
+    assert_eq!(stdout(&output), "changed.txt: FAILED\n");
+
+    let output = run(&dir, &["-c", "-"], format!("{}  abc.txt\n", ABC).as_bytes());
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "abc.txt: OK\n");
+}
+
+#[test]
+fn test_check_status() {
+    let dir = TempDir::new("status");
+    dir.write("abc.txt", "abc");
+    dir.write("changed.txt", "abd");
+    dir.write("good.sha256", format!("{}  abc.txt\n", ABC));
+    dir.write("bad.sha256", format!("{}  changed.txt\n", ABC));
+
+    let output = run(&dir, &["--check", "--status", "good.sha256"], b"");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty() && output.stderr.is_empty());
+
+    let output = run(&dir, &["--check", "--status", "bad.sha256"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty() && output.stderr.is_empty());
+}
+
+#[test]
+fn test_check_strict() {
+    let dir = TempDir::new("strict");
+    dir.write("abc.txt", "abc");
+    dir.write("list.sha256", format!("{}  abc.txt\nnot a checksum\n", ABC));
+
+    let output = run(&dir, &["-c", "list.sha256"], b"");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "abc.txt: OK\n");
+    assert_eq!(
+        stderr(&output),
+        "sha256: WARNING: 1 line is improperly formatted\n"
+    );
+
+    let output = run(&dir, &["-c", "--strict", "list.sha256"], b"");
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = run(&dir, &["-c", "-w", "list.sha256"], b"");
+    assert!(stderr(&output)
+        .starts_with("sha256: list.sha256: 2: improperly formatted SHA256 checksum line\n"));
+}
+
+#[test]
+fn test_check_missing_files() {
+    let dir = TempDir::new("ignore-missing");
+    dir.write("abc.txt", "abc");
+    dir.write(
+        "list.sha256",
+        format!("{}  abc.txt\n{}  missing\n", ABC, ABC),
+    );
+
+    let output = run(&dir, &["-c", "list.sha256"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "abc.txt: OK\nmissing: FAILED open or read\n"
+    );
+
+    let output = run(&dir, &["-c", "--ignore-missing", "list.sha256"], b"");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "abc.txt: OK\n");
+}
+
+/// Names that aren't UTF-8 are hashed, printed and checked byte for byte.
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_names() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = TempDir::new("non-utf8");
+    let name = OsStr::from_bytes(b"n\xff.bin");
+    dir.write(name, "abc");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_sha256"))
+        .arg(name)
+        .current_dir(&dir.0)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let mut line = format!("{}  ", ABC).into_bytes();
+    line.extend_from_slice(b"n\xff.bin\n");
+    assert_eq!(output.stdout, line);
+
+    dir.write("list.sha256", &line);
+    let output = run(&dir, &["-c", "list.sha256"], b"");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"n\xff.bin: OK\n");
+}